@@ -1,54 +1,191 @@
 use std::{
-    cell::RefCell,
-    collections::{BTreeMap, VecDeque},
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
     fmt,
     rc::Rc,
     sync::mpsc::{Receiver, Sender, TryRecvError},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
     MyTabViewer,
-    serialcomms::{attempt_handshake, get_serial_ports, ramp_duty, set_duty, set_frequency},
-    tabs::{Measurement, MyTab},
-    threading::ThreadMessage,
+    control::{AutotuneMethod, AutotuneSettings, Autotuner, PidGains, Regulator},
+    model::{self, ConverterParams},
+    ramp::{FrequencyRamp, RampProfile, RampShape},
+    serialcomms::{
+        BAUDRATES, Capabilities, DeviceError, DeviceInfo, DeviceSettings, DutyQ9, FrequencyHz,
+        SerialConfig, get_serial_ports, usb_serial_number,
+    },
+    tabs::{Channel, MeasBuffer, MyTab},
+    threading::{LinkHealth, ProbeMatch, SerialEvent, SerialRequest, Setting, ThreadMessage},
 };
 use anyhow::{Error, Result};
 use chrono::TimeDelta;
-use egui::{Color32, FontData, FontDefinitions, FontFamily, FontId, Id, Modal, RichText, Ui};
+use egui::{
+    Color32, FontData, FontDefinitions, FontFamily, FontId, Id, Key, KeyboardShortcut, Modal,
+    Modifiers, RichText, Ui,
+};
 use egui_dock::{DockArea, DockState, NodeIndex, Style};
-use log::{debug, error};
-use serialport::{SerialPort, SerialPortInfo};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serialport::{DataBits, FlowControl, Parity, SerialPortInfo, StopBits};
+
+/// Magnitud que el usuario fija directamente en el panel de ajustes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SetpointMode {
+    Duty,
+    /// El ciclo de trabajo se calcula a partir del voltaje de salida pedido
+    /// con el modelo ideal del convertidor.
+    Voltage,
+}
+
+/// Origen del voltaje de entrada con el que se evalúa el modelo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum VinSource {
+    Manual,
+    /// Medido por el dispositivo, consultado periódicamente.
+    Device,
+    /// Canal `Vin` de la última muestra del monitor que lo reporte.
+    Monitor,
+}
+
+impl VinSource {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Manual => "Manual",
+            Self::Device => "Dispositivo",
+            Self::Monitor => "Monitor",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SerialStatus {
+    Disconnected,
+    Probing,
+    Connecting,
+    Connected,
+    /// El puerto desapareció y el hilo serial espera a que vuelva.
+    Lost,
+}
+
+/// Estado de un ajuste enviado al hilo serial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingState {
+    Idle,
+    Pending,
+    Acknowledged,
+    Failed,
+}
+
+impl SettingState {
+    fn show(self, ui: &mut Ui) {
+        match self {
+            Self::Idle => {}
+            Self::Pending => {
+                ui.spinner()
+                    .on_hover_text("Esperando confirmación del dispositivo");
+            }
+            Self::Acknowledged => {
+                ui.colored_label(Color32::GREEN, "✔")
+                    .on_hover_text("Confirmado por el dispositivo");
+            }
+            Self::Failed => {
+                ui.colored_label(ui.visuals().error_fg_color, "✖")
+                    .on_hover_text("El dispositivo no confirmó el ajuste");
+            }
+        }
+    }
+}
 
 pub struct SepicApp {
     rx: Receiver<ThreadMessage>,
     tx: Sender<ThreadMessage>,
+    serial_rx: Receiver<SerialEvent>,
+    serial_tx: Sender<SerialRequest>,
 
     available_ports: Vec<Rc<SerialPortInfo>>,
     port_info: Option<Rc<SerialPortInfo>>,
-    serial_port: Option<Box<dyn SerialPort>>,
-    baudrate: u32,
-
-    duty_cycle: Rc<f32>,
-    frequency: Rc<f32>,
+    serial_status: SerialStatus,
+    serial_config: SerialConfig,
+    /// Configuración usada por última vez con cada dispositivo, indexada por
+    /// número de serie USB o, si no lo tiene, por nombre de puerto.
+    serial_configs: BTreeMap<String, SerialConfig>,
+    probe_matches: Vec<ProbeMatch>,
+
+    duty_cycle: Rc<Cell<DutyQ9>>,
+    frequency: Rc<Cell<FrequencyHz>>,
+    setpoint_mode: SetpointMode,
+    target_voltage: f32,
+    vin_source: VinSource,
+    manual_vin: f32,
+    device_vin: Option<f32>,
+    vin_requested: Option<Instant>,
+    converter: Rc<Cell<ConverterParams>>,
+    /// Voltaje de entrada vigente, compartido con las pestañas.
+    vin: Rc<Cell<f32>>,
+    ramp_profile: RampProfile,
+    frequency_ramp: FrequencyRamp,
+    regulator: Regulator,
+    autotune_settings: AutotuneSettings,
+    autotuner: Option<Autotuner>,
+    /// Resultado del último experimento de sintonía, a la espera de que se
+    /// acepte o se descarte.
+    autotune_result: Option<Result<PidGains, String>>,
+    duty_state: SettingState,
+    frequency_state: SettingState,
+    device_info: Option<DeviceInfo>,
+    device_settings: Option<DeviceSettings>,
+    awaiting_readback: bool,
+    link_health: Option<LinkHealth>,
+    /// Los controles de ajuste quedan bloqueados tras un paro de emergencia
+    /// hasta que el usuario los rearma.
+    stopped: bool,
+    /// Al salir se deja el convertidor con el ciclo de trabajo actual en vez
+    /// de llevarlo a cero.
+    leave_running: bool,
+    stop_monitor_on_exit: bool,
 
     monitor_address: String,
     monitor_port: u16,
     monitor_connected: bool,
+    telemetry_received: u64,
+    telemetry_malformed: u64,
+    /// Motivo por el que se descartó el último datagrama del monitor.
+    telemetry_error: Option<String>,
 
-    meas_data: Rc<RefCell<VecDeque<Measurement>>>,
+    meas_data: Rc<RefCell<MeasBuffer>>,
 
     error_modal: Option<AppError>,
     tree: DockState<MyTab>,
 }
 
 impl SepicApp {
-    const MAX_SAMPLES: usize = 1000;
+    /// Historial del monitor que se conserva y se grafica.
+    const HISTORY: TimeDelta = TimeDelta::minutes(5);
+    const SERIAL_CONFIGS_KEY: &str = "serial_configs";
+    const RAMP_PROFILE_KEY: &str = "ramp_profile";
+    const INPUT_VOLTAGE_KEY: &str = "input_voltage";
+    const CONVERTER_KEY: &str = "converter";
+    const VIN_SOURCE_KEY: &str = "vin_source";
+    const VIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+    const FREQUENCY_RAMP_KEY: &str = "frequency_ramp";
+    const REGULATOR_KEY: &str = "regulator";
+    const AUTOTUNE_KEY: &str = "autotune";
+    const LEAVE_RUNNING_KEY: &str = "leave_running";
+    const STOP_MONITOR_KEY: &str = "stop_monitor_on_exit";
+    const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+    const MONITOR_STOP_TIMEOUT: Duration = Duration::from_millis(500);
+    /// No se usa Ctrl+Espacio porque la capturan Spotlight en macOS y los
+    /// métodos de entrada en Linux antes de que llegue a la ventana.
+    const STOP_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::SHIFT, Key::Escape);
 
     pub fn new(
         cc: &eframe::CreationContext<'_>,
         tx: Sender<ThreadMessage>,
         rx: Receiver<ThreadMessage>,
+        serial_tx: Sender<SerialRequest>,
+        serial_rx: Receiver<SerialEvent>,
     ) -> Self {
         let mut fonts = FontDefinitions::default();
         fonts.font_data.insert(
@@ -74,81 +211,467 @@ impl SepicApp {
         cc.egui_ctx.set_fonts(fonts);
         cc.egui_ctx.set_zoom_factor(1.5);
 
-        let frequency = Rc::new(60e3);
-        let duty_cycle = Rc::new(0.0);
+        let frequency = Rc::new(Cell::new(FrequencyHz::from_raw(60_000)));
+        let duty_cycle = Rc::new(Cell::new(DutyQ9::ZERO));
         let tspan = 100.0;
 
-        let meas_data = Rc::new(RefCell::new(VecDeque::with_capacity(Self::MAX_SAMPLES)));
+        let meas_data = Rc::new(RefCell::new(MeasBuffer::with_window(Self::HISTORY)));
+        let converter = Rc::new(Cell::new(
+            stored(cc, Self::CONVERTER_KEY).unwrap_or_default(),
+        ));
+        let vin = Rc::new(Cell::new(0.0));
 
         let mut tree = DockState::new(vec![
             MyTab::pwm_window(Rc::clone(&frequency), Rc::clone(&duty_cycle), tspan),
-            MyTab::meas_window(Rc::clone(&meas_data), TimeDelta::minutes(5)),
+            MyTab::meas_window(Rc::clone(&meas_data), Self::HISTORY),
+            MyTab::ripple_window(
+                Rc::clone(&frequency),
+                Rc::clone(&duty_cycle),
+                Rc::clone(&converter),
+                Rc::clone(&vin),
+            ),
         ]);
         let [_, _] =
             tree.main_surface_mut()
                 .split_below(NodeIndex::root(), 0.75, vec![MyTab::log_window()]);
 
-        Self {
+        let serial_configs = stored(cc, Self::SERIAL_CONFIGS_KEY).unwrap_or_default();
+        let leave_running = stored(cc, Self::LEAVE_RUNNING_KEY).unwrap_or(false);
+        let stop_monitor_on_exit = stored(cc, Self::STOP_MONITOR_KEY).unwrap_or(true);
+
+        let mut app = Self {
             rx,
             tx,
+            serial_rx,
+            serial_tx,
 
-            available_ports: get_serial_ports(),
+            available_ports: Vec::new(),
             port_info: None,
-            serial_port: None,
-            baudrate: 9600,
+            serial_status: SerialStatus::Disconnected,
+            serial_config: SerialConfig::default(),
+            serial_configs,
+            probe_matches: Vec::new(),
 
             duty_cycle,
             frequency,
+            setpoint_mode: SetpointMode::Duty,
+            target_voltage: 0.0,
+            vin_source: stored(cc, Self::VIN_SOURCE_KEY).unwrap_or(VinSource::Manual),
+            manual_vin: stored(cc, Self::INPUT_VOLTAGE_KEY).unwrap_or(24.0),
+            device_vin: None,
+            vin_requested: None,
+            converter,
+            vin,
+            ramp_profile: stored(cc, Self::RAMP_PROFILE_KEY).unwrap_or_default(),
+            frequency_ramp: stored(cc, Self::FREQUENCY_RAMP_KEY).unwrap_or_default(),
+            regulator: Regulator::new(stored(cc, Self::REGULATOR_KEY).unwrap_or_default()),
+            autotune_settings: stored(cc, Self::AUTOTUNE_KEY).unwrap_or_default(),
+            autotuner: None,
+            autotune_result: None,
+            duty_state: SettingState::Idle,
+            frequency_state: SettingState::Idle,
+            device_info: None,
+            device_settings: None,
+            awaiting_readback: false,
+            link_health: None,
+            stopped: false,
+            leave_running,
+            stop_monitor_on_exit,
 
             monitor_address: "esp32-pelele.local".to_owned(),
             monitor_port: 4444,
             monitor_connected: false,
+            telemetry_received: 0,
+            telemetry_malformed: 0,
+            telemetry_error: None,
 
             meas_data,
 
             tree,
             error_modal: None,
-        }
+        };
+        app.update_serial_ports();
+        app
     }
 
     fn update_serial_ports(&mut self) {
-        self.available_ports = get_serial_ports();
+        self.available_ports = get_serial_ports().unwrap_or_else(|e| {
+            error!("Ocurrió un error al obtener la lista de puertos disponibles: {e}");
+            Vec::new()
+        });
     }
-}
 
-impl SepicApp {
-    fn poll_messages(&mut self) -> Result<()> {
-        let mut message = ThreadMessage::None;
-        match self.rx.try_recv() {
-            Ok(msg) => message = msg,
-            Err(e) => {
-                if matches!(e, TryRecvError::Disconnected) {
-                    return Err(e.into());
-                }
-            }
+    fn send_serial(&self, request: SerialRequest) {
+        self.serial_tx.send(request).unwrap_or_else(|e| {
+            error!("Error en la comunicación con el hilo serial: {e}");
+        });
+    }
+
+    fn read_back(&mut self) {
+        self.awaiting_readback = true;
+        self.send_serial(SerialRequest::ReadSettings);
+    }
+
+    fn poll_serial_events(&mut self) {
+        while let Ok(event) = self.serial_rx.try_recv() {
+            self.handle_serial_event(event);
+        }
+    }
+
+    fn connected(&mut self, port_name: &str, info: DeviceInfo, settings: DeviceSettings) {
+        debug!("Conectado al dispositivo en `{port_name}`");
+        self.update_serial_ports();
+        self.port_info = self
+            .available_ports
+            .iter()
+            .find(|port| port.port_name == port_name)
+            .cloned()
+            .or_else(|| self.port_info.take());
+        self.serial_status = SerialStatus::Connected;
+        if let Some(port) = &self.port_info {
+            self.serial_configs
+                .insert(Self::config_key(port), self.serial_config.clone());
         }
+        self.device_info = Some(info);
+        self.link_health = Some(LinkHealth::Healthy);
+        self.frequency.set(settings.frequency);
+        self.duty_cycle.set(settings.duty_cycle);
+        self.device_settings = Some(settings);
+    }
 
-        match message {
-            ThreadMessage::ConnectionEstablished => {
-                self.monitor_connected = true;
+    fn handle_serial_event(&mut self, event: SerialEvent) {
+        match event {
+            SerialEvent::Connected {
+                port_name,
+                info,
+                settings,
+            } => self.connected(&port_name, info, settings),
+            SerialEvent::Reconnected {
+                port_name,
+                info,
+                settings,
+                duty_cycle,
+            } => {
+                self.connected(&port_name, info, settings);
+                if !self.stopped && duty_cycle != settings.duty_cycle {
+                    debug!("Restaurando el ciclo de trabajo a {duty_cycle}");
+                    self.request_duty(duty_cycle);
+                    self.duty_cycle.set(duty_cycle);
+                }
+            }
+            SerialEvent::OpenFailed { port_name, error } => {
+                self.disconnect();
+                self.error_modal = Some(AppError::connection(&port_name, &error));
+            }
+            SerialEvent::HandshakeFailed { port_name, error } => {
+                self.disconnect();
+                self.error_modal = Some(AppError::handshake(&port_name, &error));
+            }
+            SerialEvent::Applied(setting) => {
+                *self.setting_state(setting) = SettingState::Acknowledged;
+            }
+            SerialEvent::Failed { setting, error } => {
+                *self.setting_state(setting) = SettingState::Failed;
+                self.error_modal = Some(AppError::setting(setting.label(), &error));
+            }
+            SerialEvent::Readback(settings) => {
+                self.awaiting_readback = false;
+                if settings.diverges_from(self.frequency.get(), self.duty_cycle.get()) {
+                    warn!(
+                        "El dispositivo reporta {} y {}, pero se esperaba {} y {}",
+                        settings.frequency,
+                        settings.duty_cycle,
+                        self.frequency.get(),
+                        self.duty_cycle.get()
+                    );
+                }
+                self.device_settings = Some(settings);
+            }
+            SerialEvent::ReadbackFailed(error) => {
+                self.awaiting_readback = false;
+                self.error_modal = Some(AppError::readback(&error));
+            }
+            SerialEvent::LinkLost { port_name } => {
+                warn!("Se desconectó el puerto `{port_name}`, esperando a que vuelva");
+                self.serial_status = SerialStatus::Lost;
+                self.link_health = None;
+                self.regulator.disable();
+                self.autotuner = None;
+                self.awaiting_readback = false;
+                self.duty_state = SettingState::Idle;
+                self.frequency_state = SettingState::Idle;
             }
-            ThreadMessage::Data(measurement) => {
-                let mut data = self.meas_data.borrow_mut();
-                if data.len() == Self::MAX_SAMPLES {
-                    data.pop_front();
+            SerialEvent::InputVoltage(vin) => self.device_vin = Some(vin),
+            SerialEvent::InputVoltageFailed(error) => {
+                warn!("No se pudo leer el voltaje de entrada: {error}");
+                self.device_vin = None;
+            }
+            SerialEvent::LinkHealth(health) => self.link_health = Some(health),
+            // Solo se pide el apagado al salir, y allí se espera esta respuesta.
+            SerialEvent::ShutdownFinished(_) => {}
+            SerialEvent::Stopped => self.duty_state = SettingState::Acknowledged,
+            SerialEvent::StopFailed(error) => {
+                self.duty_state = SettingState::Failed;
+                self.error_modal = Some(AppError::stop(&error));
+            }
+            SerialEvent::ProbeFinished(matches) => {
+                self.serial_status = SerialStatus::Disconnected;
+                match matches.as_slice() {
+                    [] => warn!("No se encontró ningún controlador SEPIC"),
+                    [found] => {
+                        debug!("Conectando automáticamente a `{}`", found.port_name);
+                        self.connect_to(&found.port_name, found.baudrate);
+                    }
+                    _ => debug!("Se encontraron {} controladores", matches.len()),
                 }
-                data.push_back(measurement);
+                self.probe_matches = matches;
             }
-            _ => {}
         }
+    }
+
+    fn config_key(port: &SerialPortInfo) -> String {
+        usb_serial_number(port).map_or_else(|| port.port_name.clone(), str::to_owned)
+    }
+
+    /// Carga la configuración recordada para el puerto, si existe.
+    fn load_serial_config(&mut self, port: &SerialPortInfo) {
+        if let Some(config) = self.serial_configs.get(&Self::config_key(port)) {
+            debug!("Usando la configuración guardada para `{}`", port.port_name);
+            self.serial_config = config.clone();
+        }
+    }
+
+    fn connect(&mut self, port_name: String) {
+        debug!(
+            "Conectando a `{port_name}` a {} baud",
+            self.serial_config.baudrate
+        );
+        self.send_serial(SerialRequest::Connect {
+            port_name,
+            config: self.serial_config.clone(),
+        });
+        self.serial_status = SerialStatus::Connecting;
+        self.device_info = None;
+        self.device_settings = None;
+    }
 
-        Ok(())
+    fn connect_to(&mut self, port_name: &str, baudrate: u32) {
+        self.update_serial_ports();
+        self.port_info = self
+            .available_ports
+            .iter()
+            .find(|port| port.port_name == port_name)
+            .cloned();
+        if let Some(port) = self.port_info.clone() {
+            self.load_serial_config(&port);
+        }
+        // La velocidad encontrada al sondear manda sobre la guardada.
+        self.serial_config.baudrate = baudrate;
+        self.connect(port_name.to_owned());
+    }
+
+    fn show_probe_matches(&mut self, ui: &mut Ui) {
+        let mut selected = None;
+        for found in &self.probe_matches {
+            ui.horizontal(|ui| {
+                if ui.small_button("Conectar").clicked() {
+                    selected = Some((found.port_name.clone(), found.baudrate));
+                }
+                ui.label(format!(
+                    "{} @ {} — {} ({})",
+                    found.port_name, found.baudrate, found.info.id, found.info.firmware
+                ));
+            });
+        }
+
+        if let Some((port_name, baudrate)) = selected {
+            self.connect_to(&port_name, baudrate);
+        }
+    }
+
+    fn setting_state(&mut self, setting: Setting) -> &mut SettingState {
+        match setting {
+            Setting::DutyCycle => &mut self.duty_state,
+            Setting::Frequency => &mut self.frequency_state,
+        }
     }
 
-    fn update_menubar(ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn disconnect(&mut self) {
+        self.serial_status = SerialStatus::Disconnected;
+        self.port_info = None;
+        self.device_info = None;
+        self.device_settings = None;
+        self.awaiting_readback = false;
+        self.link_health = None;
+        self.device_vin = None;
+        self.regulator.disable();
+        self.autotuner = None;
+        self.duty_state = SettingState::Idle;
+        self.frequency_state = SettingState::Idle;
+    }
+
+    fn can_stop(&self) -> bool {
+        matches!(
+            self.serial_status,
+            SerialStatus::Connecting | SerialStatus::Connected | SerialStatus::Lost
+        )
+    }
+
+    fn emergency_stop(&mut self) {
+        warn!("Paro de emergencia activado");
+        self.stopped = true;
+        self.regulator.disable();
+        self.autotuner = None;
+        self.duty_cycle.set(DutyQ9::ZERO);
+        self.duty_state = SettingState::Pending;
+        self.send_serial(SerialRequest::EmergencyStop);
+    }
+
+    fn show_emergency_stop(&mut self, ui: &mut Ui) {
+        let button = egui::Button::new(
+            RichText::new("⏹ PARO DE EMERGENCIA")
+                .strong()
+                .color(Color32::WHITE),
+        )
+        .fill(Color32::DARK_RED)
+        .min_size(egui::vec2(ui.available_width(), 40.0));
+
+        if ui
+            .add_enabled(self.can_stop(), button)
+            .on_hover_text(ui.ctx().format_shortcut(&Self::STOP_SHORTCUT))
+            .clicked()
+        {
+            self.emergency_stop();
+        }
+
+        if self.stopped {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::RED, "Salida detenida");
+                if ui.button("Rearmar").clicked() {
+                    warn!("Controles rearmados después del paro de emergencia");
+                    self.stopped = false;
+                }
+            });
+        }
+    }
+
+    fn show_link_health(&self, ui: &mut Ui) {
+        let Some(health) = self.link_health else {
+            return;
+        };
+
+        let (color, text) = match health {
+            LinkHealth::Healthy => (Color32::GREEN, "Enlace activo".to_owned()),
+            LinkHealth::Late { missed } => (
+                ui.visuals().warn_fg_color,
+                format!("{missed} heartbeat(s) sin respuesta"),
+            ),
+            LinkHealth::Unresponsive => (Color32::RED, "El dispositivo no responde".to_owned()),
+        };
+        ui.colored_label(color, "●").on_hover_text(text);
+    }
+
+    fn show_device_info(ui: &mut Ui, info: &DeviceInfo) {
+        if info.is_legacy() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ Firmware sin identificación, se usan los límites por defecto",
+            );
+            return;
+        }
+
+        let (major, minor) = info.protocol;
+        egui::Grid::new("device_info")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Dispositivo");
+                ui.label(&info.id);
+                ui.end_row();
+                ui.label("Firmware");
+                ui.label(&info.firmware);
+                ui.end_row();
+                ui.label("Protocolo");
+                ui.label(format!("{major}.{minor}"));
+                ui.end_row();
+            });
+    }
+
+    fn show_resolution(ui: &mut Ui, capabilities: &Capabilities) {
+        ui.label(
+            RichText::new(format!(
+                "Resolución: {:.4} % · {} Hz (límites {}–{}, {})",
+                DutyQ9::RESOLUTION,
+                FrequencyHz::RESOLUTION,
+                capabilities.min_frequency,
+                capabilities.max_frequency,
+                capabilities.max_duty
+            ))
+            .small(),
+        );
+    }
+
+    fn show_device_settings(&self, ui: &mut Ui) {
+        let Some(settings) = self.device_settings else {
+            return;
+        };
+
+        ui.label(format!(
+            "Dispositivo: {}, {}",
+            settings.frequency, settings.duty_cycle
+        ));
+        if !self.awaiting_readback
+            && settings.diverges_from(self.frequency.get(), self.duty_cycle.get())
+        {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ Los valores del dispositivo no coinciden con los ajustados",
+            );
+        }
+    }
+}
+
+impl SepicApp {
+    /// Procesa todos los mensajes pendientes del hilo auxiliar, de modo que
+    /// la interfaz no se atrase cuando el monitor envía más rápido que los
+    /// cuadros por segundo.
+    fn poll_messages(&mut self) -> Result<()> {
+        loop {
+            let message = match self.rx.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+
+            match message {
+                ThreadMessage::ConnectionEstablished => {
+                    self.monitor_connected = true;
+                }
+                ThreadMessage::Batch(measurements) => {
+                    self.telemetry_received += measurements.len() as u64;
+                    self.meas_data.borrow_mut().extend(measurements);
+                }
+                ThreadMessage::Malformed(error) => {
+                    self.telemetry_malformed += 1;
+                    self.telemetry_error = Some(error.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn update_menubar(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
                 ui.menu_button("Archivo", |ui| {
+                    ui.checkbox(
+                        &mut self.leave_running,
+                        "Dejar el convertidor encendido al salir",
+                    );
+                    ui.checkbox(
+                        &mut self.stop_monitor_on_exit,
+                        "Detener el monitor al salir",
+                    );
+                    ui.separator();
                     if ui.button("Salir").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
@@ -161,11 +684,15 @@ impl SepicApp {
     }
 
     fn update_settingsbar(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut duty_cycle = *self.duty_cycle;
-        let mut frequency = *self.frequency;
+        let mut duty_cycle = self.duty_cycle.get().percent();
+        let mut frequency = self.frequency.get().hz();
 
         egui::SidePanel::left("Ajustes").show(ctx, |ui| {
-            ui.heading("SEPIC");
+            ui.horizontal(|ui| {
+                ui.heading("SEPIC");
+                self.show_link_health(ui);
+            });
+            self.show_emergency_stop(ui);
             ui.vertical(|ui| {
                 self.update_serial_settings(ui);
 
@@ -175,79 +702,381 @@ impl SepicApp {
 
                 ui.separator();
 
-                duty_cycle = *self.duty_cycle;
-                frequency = *self.frequency;
+                self.update_ramp_settings(ui);
 
-                let mut ui_builder = egui::UiBuilder::new();
-                if self.serial_port.is_none() {
-                    ui_builder = ui_builder.disabled();
-                }
+                ui.separator();
 
-                ui.scope_builder(ui_builder, |ui| {
-                    ui.add(
-                        egui::Slider::new(&mut duty_cycle, 0.0..=75.0)
-                            .text("(%) Duty cycle")
-                            .update_while_editing(false)
-                            .custom_formatter(|n, _| format!("{n:02.1}")),
-                    );
-                    ui.add(
-                        egui::Slider::new(&mut frequency, 60e3..=120e3)
-                            .text("(kHz) Frecuencia")
-                            .update_while_editing(false)
-                            .custom_formatter(|n, _| {
-                                let n = n / 1e3;
-                                format!("{n:02.1}")
+                self.update_regulator_settings(ui);
+
+                ui.separator();
+
+                let capabilities = self
+                    .device_info
+                    .as_ref()
+                    .map(|info| info.capabilities)
+                    .unwrap_or_default();
+
+                duty_cycle = capabilities.clamp_duty(self.duty_cycle.get()).percent();
+                frequency = capabilities.clamp_frequency(self.frequency.get()).hz();
+
+                self.update_setpoints(ui, &capabilities, &mut duty_cycle, &mut frequency);
+
+                ui.separator();
+
+                self.update_converter_settings(ui);
+
+                ui.separator();
+                ui.add_space(ui.available_height() - 100.0);
+
+                self.show_expected_output(ui, duty_cycle, frequency);
+            });
+        });
+
+        self.apply_settings(duty_cycle, frequency);
+    }
+
+    /// Voltaje de entrada con el que se evalúa el modelo. Mientras la fuente
+    /// elegida no tenga lectura se usa el valor manual.
+    fn input_voltage(&self) -> f32 {
+        self.measured_vin().unwrap_or(self.manual_vin)
+    }
+
+    fn measured_vin(&self) -> Option<f32> {
+        match self.vin_source {
+            VinSource::Manual => None,
+            VinSource::Device => self.device_vin,
+            VinSource::Monitor => self
+                .meas_data
+                .borrow()
+                .latest(Channel::Vin)
+                .map(|vin| vin as f32),
+        }
+    }
+
+    fn poll_input_voltage(&mut self) {
+        let supported = self
+            .device_info
+            .as_ref()
+            .is_some_and(|info| info.capabilities.input_voltage);
+        if self.vin_source != VinSource::Device
+            || self.serial_status != SerialStatus::Connected
+            || !supported
+        {
+            return;
+        }
+
+        let now = Instant::now();
+        if self
+            .vin_requested
+            .is_some_and(|requested| now < requested + Self::VIN_POLL_INTERVAL)
+        {
+            return;
+        }
+        self.vin_requested = Some(now);
+        self.send_serial(SerialRequest::ReadInputVoltage);
+    }
+
+    /// Selector de la fuente del voltaje de entrada. Devuelve si el usuario
+    /// cambió la fuente o el valor manual; las variaciones de una lectura no
+    /// reenvían el ciclo de trabajo calculado.
+    fn show_input_voltage(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        // El monitor solo sirve de fuente si reporta un canal `Vin`; con el
+        // formato anterior la única magnitud es `Vout`.
+        let monitor_vin = self.meas_data.borrow().latest(Channel::Vin).is_some();
+
+        ui.horizontal(|ui| {
+            egui::containers::ComboBox::from_id_salt("vin_source")
+                .selected_text(self.vin_source.label())
+                .show_ui(ui, |ui| {
+                    for source in [VinSource::Manual, VinSource::Device, VinSource::Monitor] {
+                        let available = source != VinSource::Monitor || monitor_vin;
+                        changed |= ui
+                            .add_enabled_ui(available, |ui| {
+                                ui.selectable_value(&mut self.vin_source, source, source.label())
                             })
-                            .custom_parser(|s| s.parse::<f64>().map(|n| n * 1000.0).ok()),
-                    );
+                            .inner
+                            .on_disabled_hover_text("El monitor no reporta el canal Vin")
+                            .changed();
+                    }
                 });
+            ui.label("Voltaje de entrada");
+        });
 
-                ui.separator();
-                ui.add_space(ui.available_height() - 60.0);
+        if self.vin_source == VinSource::Manual {
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.manual_vin)
+                        .range(0.1..=1000.0)
+                        .speed(0.1)
+                        .suffix(" V"),
+                )
+                .changed();
+        } else if let Some(vin) = self.measured_vin() {
+            ui.label(format!("{vin:.2} V medidos"));
+        } else {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Sin lectura, se usan {:.2} V", self.manual_vin),
+            );
+        }
 
-                ui.label(egui::RichText::new("Voltaje de salida esperado").heading());
-                ui.horizontal(|ui| {
-                    ui.label(
-                        egui::RichText::new(format!(
-                            "{:.2}",
-                            (24.0 * duty_cycle / 100.0) / (1.0 - (duty_cycle / 100.0))
-                        ))
-                        .font(FontId::new(40.0, FontFamily::Name("7-segment".into()))),
-                    );
-                    ui.label(egui::RichText::new("V").size(35.0).monospace());
+        changed
+    }
+
+    /// Salida estimada con el modelo con pérdidas, junto a la del modelo ideal
+    /// y el modo de conducción.
+    fn show_expected_output(&self, ui: &mut Ui, duty_cycle: f32, frequency: f32) {
+        let (Ok(duty_cycle), Ok(frequency)) = (
+            DutyQ9::from_percent(duty_cycle),
+            FrequencyHz::from_hz(frequency),
+        ) else {
+            return;
+        };
+        let vin = self.input_voltage();
+        let point = self
+            .converter
+            .get()
+            .operating_point(vin, duty_cycle, frequency);
+
+        ui.label(egui::RichText::new("Voltaje de salida esperado").heading());
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!("{:.2}", point.output))
+                    .font(FontId::new(40.0, FontFamily::Name("7-segment".into()))),
+            );
+            ui.label(egui::RichText::new("V").size(35.0).monospace());
+        });
+        ui.label(format!(
+            "Ideal: {:.2} V · {}",
+            model::ideal_output(vin, duty_cycle),
+            point.mode.label()
+        ))
+        .on_hover_text(format!(
+            "K = {:.3}, Kcrit = {:.3}; DCM con cargas mayores a {:.1} Ω",
+            point.k, point.k_crit, point.critical_load
+        ));
+    }
+
+    fn update_converter_settings(&self, ui: &mut Ui) {
+        let mut params = self.converter.get();
+
+        ui.collapsing("Parámetros del convertidor", |ui| {
+            egui::Grid::new("converter_params")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (label, value, scale, suffix) in [
+                        ("L1", &mut params.l1, 1e6, " µH"),
+                        ("L2", &mut params.l2, 1e6, " µH"),
+                        ("Cc", &mut params.cc, 1e6, " µF"),
+                        ("Co", &mut params.co, 1e6, " µF"),
+                        ("Rds(on)", &mut params.rds_on, 1e3, " mΩ"),
+                        ("Vf diodo", &mut params.vf, 1.0, " V"),
+                        ("DCR inductores", &mut params.dcr, 1e3, " mΩ"),
+                        ("Carga", &mut params.load, 1.0, " Ω"),
+                    ] {
+                        ui.label(label);
+                        ui.add(
+                            egui::DragValue::from_get_set(|set| {
+                                if let Some(set) = set {
+                                    *value = (set / scale).max(0.0) as f32;
+                                }
+                                f64::from(*value) * scale
+                            })
+                            .speed(0.1)
+                            .suffix(suffix),
+                        );
+                        ui.end_row();
+                    }
                 });
-            });
         });
 
-        if let Some(serial_port) = self.serial_port.as_mut() {
-            if duty_cycle != *self.duty_cycle {
-                debug!("Actualizando ciclo de trabajo a {}", self.duty_cycle);
-                if (duty_cycle - *self.duty_cycle).abs() > 15.0 {
-                    ramp_duty(serial_port, duty_cycle, *self.duty_cycle, 1000).unwrap_or_else(
-                        |e| {
-                            error!("No se pudo actualizar el ciclo de trabajo: {e}");
-                            self.error_modal = Some(AppError::setting("duty cycle", &e));
-                        },
-                    );
-                } else {
-                    set_duty(serial_port, *self.duty_cycle).unwrap_or_else(|e| {
-                        error!("No se pudo actualizar el ciclo de trabajo: {e}");
-                        self.error_modal = Some(AppError::setting("duty cycle", &e));
+        self.converter.set(params);
+    }
+
+    fn update_setpoints(
+        &mut self,
+        ui: &mut Ui,
+        capabilities: &Capabilities,
+        duty_cycle: &mut f32,
+        frequency: &mut f32,
+    ) {
+        let prev_mode = self.setpoint_mode;
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut self.setpoint_mode,
+                SetpointMode::Duty,
+                "Ciclo de trabajo",
+            );
+            ui.selectable_value(
+                &mut self.setpoint_mode,
+                SetpointMode::Voltage,
+                "Voltaje de salida",
+            );
+        });
+        if prev_mode != self.setpoint_mode && self.setpoint_mode == SetpointMode::Voltage {
+            self.target_voltage = model::ideal_output(self.input_voltage(), self.duty_cycle.get());
+        }
+
+        let vin_changed = self.show_input_voltage(ui);
+
+        let mut ui_builder = egui::UiBuilder::new();
+        if self.serial_status != SerialStatus::Connected || self.stopped {
+            ui_builder = ui_builder.disabled();
+        }
+
+        ui.scope_builder(ui_builder, |ui| {
+            match self.setpoint_mode {
+                SetpointMode::Duty => {
+                    ui.horizontal(|ui| {
+                        ui.add_enabled(
+                            self.duty_is_manual(),
+                            egui::Slider::new(duty_cycle, 0.0..=capabilities.max_duty.percent())
+                                .text("(%) Duty cycle")
+                                .update_while_editing(false)
+                                .custom_formatter(|n, _| format!("{n:02.1}")),
+                        );
+                        self.duty_state.show(ui);
                     });
                 }
+                SetpointMode::Voltage => {
+                    self.show_voltage_setpoint(ui, capabilities.max_duty, vin_changed, duty_cycle);
+                }
             }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(
+                        frequency,
+                        capabilities.min_frequency.hz()..=capabilities.max_frequency.hz(),
+                    )
+                    .text("(kHz) Frecuencia")
+                    .update_while_editing(false)
+                    .custom_formatter(|n, _| {
+                        let n = n / 1e3;
+                        format!("{n:02.1}")
+                    })
+                    .custom_parser(|s| s.parse::<f64>().map(|n| n * 1000.0).ok()),
+                );
+                self.frequency_state.show(ui);
+            });
 
-            if frequency != *self.frequency {
-                debug!("Actualizando frecuencia a {}", self.frequency);
-                set_frequency(serial_port, *self.frequency).unwrap_or_else(|e| {
-                    error!("No se pudo actualizar la frecuencia: {e}");
-                    self.error_modal = Some(AppError::setting("frecuencia", &e));
-                });
+            if let (Ok(duty), Ok(freq)) = (
+                DutyQ9::from_percent(*duty_cycle),
+                FrequencyHz::from_hz(*frequency),
+            ) {
+                ui.label(format!("Valor aplicado: {duty} · {freq}"));
+            }
+            Self::show_resolution(ui, capabilities);
+
+            if ui.button("Leer del dispositivo").clicked() {
+                self.read_back();
+            }
+            self.show_device_settings(ui);
+        });
+    }
+
+    /// Campo para el voltaje de salida deseado. El ciclo de trabajo se
+    /// recalcula solo cuando cambia el voltaje pedido o el de entrada, y
+    /// luego sigue el mismo camino que el deslizador.
+    fn show_voltage_setpoint(
+        &mut self,
+        ui: &mut Ui,
+        max_duty: DutyQ9,
+        vin_changed: bool,
+        duty_cycle: &mut f32,
+    ) {
+        let manual = self.duty_is_manual();
+        let target_changed = ui
+            .horizontal(|ui| {
+                let response = ui.add_enabled(
+                    manual,
+                    egui::DragValue::new(&mut self.target_voltage)
+                        .range(0.0..=1000.0)
+                        .speed(0.1)
+                        .update_while_editing(false)
+                        .suffix(" V"),
+                );
+                ui.label("Voltaje deseado");
+                self.duty_state.show(ui);
+                response.changed()
+            })
+            .inner;
+
+        match model::ideal_duty(self.input_voltage(), self.target_voltage, max_duty) {
+            Ok(duty) => {
+                ui.label(format!("Ciclo de trabajo necesario: {duty}"));
+                if (target_changed || vin_changed) && manual && ui.is_enabled() {
+                    *duty_cycle = duty.percent();
+                }
+            }
+            Err(e) => {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {e}"));
             }
         }
+    }
 
-        self.duty_cycle = duty_cycle.into();
-        self.frequency = frequency.into();
+    /// Mientras regula o sintoniza, el lazo es el dueño del ciclo de trabajo y
+    /// los controles manuales quedan bloqueados.
+    fn duty_is_manual(&self) -> bool {
+        !self.regulator.is_enabled() && self.autotuner.is_none()
+    }
+
+    /// Pide el cambio de ciclo de trabajo con el perfil de rampa vigente. El
+    /// hilo serial decide la rampa desde el último valor que aplicó, que
+    /// puede no ser el de la interfaz si hay una rampa en curso.
+    fn request_duty(&mut self, end: DutyQ9) {
+        self.duty_state = SettingState::Pending;
+        self.awaiting_readback = true;
+        self.send_serial(SerialRequest::RampDuty {
+            end,
+            profile: self.ramp_profile,
+        });
+    }
+
+    fn apply_settings(&mut self, duty_cycle: f32, frequency: f32) {
+        let duty_cycle = match DutyQ9::from_percent(duty_cycle) {
+            Ok(duty_cycle) => duty_cycle,
+            Err(e) => {
+                error!("Ciclo de trabajo inválido: {e}");
+                self.error_modal = Some(AppError::setting("duty cycle", &Error::new(e)));
+                return;
+            }
+        };
+        let frequency = match FrequencyHz::from_hz(frequency) {
+            Ok(frequency) => frequency,
+            Err(e) => {
+                error!("Frecuencia inválida: {e}");
+                self.error_modal = Some(AppError::setting("frecuencia", &Error::new(e)));
+                return;
+            }
+        };
+
+        let prev_duty = self.duty_cycle.get();
+        let prev_frequency = self.frequency.get();
+
+        if self.serial_status == SerialStatus::Connected && !self.stopped {
+            if duty_cycle != prev_duty {
+                debug!("Actualizando ciclo de trabajo a {duty_cycle}");
+                self.request_duty(duty_cycle);
+            }
+
+            if frequency != prev_frequency {
+                debug!("Actualizando frecuencia a {frequency}");
+                self.frequency_state = SettingState::Pending;
+                self.awaiting_readback = true;
+                if self.frequency_ramp.duration().is_some() {
+                    self.send_serial(SerialRequest::RampFrequency {
+                        end: frequency,
+                        profile: self.frequency_ramp,
+                    });
+                } else {
+                    self.send_serial(SerialRequest::SetFrequency(frequency));
+                }
+            }
+        }
+
+        self.duty_cycle.set(duty_cycle);
+        self.frequency.set(frequency);
     }
 
     fn update_serial_settings(&mut self, ui: &mut Ui) {
@@ -285,67 +1114,598 @@ impl SepicApp {
                     });
             });
 
-            egui::containers::ComboBox::from_label("Baudrate")
-                .selected_text(format!("{}", self.baudrate))
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.baudrate, 9600, format!("{}", 9600));
-                    ui.selectable_value(&mut self.baudrate, 38400, format!("{}", 38400));
-                    ui.selectable_value(&mut self.baudrate, 115200, format!("{}", 115200));
-                });
+            self.show_serial_config(ui);
+
+            ui.horizontal(|ui| {
+                let idle = self.serial_status == SerialStatus::Disconnected;
+                if ui
+                    .add_enabled(idle, egui::Button::new("Auto-detectar"))
+                    .clicked()
+                {
+                    self.probe_matches.clear();
+                    self.send_serial(SerialRequest::Probe);
+                    self.serial_status = SerialStatus::Probing;
+                }
+                if self.serial_status == SerialStatus::Probing {
+                    ui.spinner();
+                    ui.label("Buscando...");
+                }
+            });
+            if self.serial_status == SerialStatus::Disconnected {
+                self.show_probe_matches(ui);
+            }
 
             let mut ui_builder = egui::UiBuilder::new();
-            if self.serial_port.is_none() {
+            if self.serial_status == SerialStatus::Disconnected {
                 ui_builder = ui_builder.disabled();
             }
 
             ui.scope_builder(ui_builder, |ui| {
-                if ui.button("Desconectar").clicked() {
-                    self.serial_port = None;
-                    self.port_info = None;
-                }
+                ui.horizontal(|ui| {
+                    if ui.button("Desconectar").clicked() {
+                        self.send_serial(SerialRequest::Disconnect);
+                        self.disconnect();
+                    }
+                    if let Some(port) = &self.port_info
+                        && ui.button("Reconectar").clicked()
+                    {
+                        self.connect(port.port_name.clone());
+                    }
+                    match self.serial_status {
+                        SerialStatus::Connecting => {
+                            ui.spinner();
+                            ui.label("Conectando...");
+                        }
+                        SerialStatus::Lost => {
+                            ui.spinner();
+                            ui.colored_label(ui.visuals().warn_fg_color, "Conexión perdida");
+                        }
+                        SerialStatus::Disconnected
+                        | SerialStatus::Probing
+                        | SerialStatus::Connected => {}
+                    }
+                });
             });
+
+            if let Some(info) = &self.device_info {
+                Self::show_device_info(ui, info);
+            }
         });
 
         if prev_port != self.port_info
-            && let Some(port) = &self.port_info
+            && let Some(port) = self.port_info.clone()
         {
             debug!("Se seleccionó nuevo puerto serial `{}`", port.port_name);
-            self.serial_port = serialport::new(&port.port_name, self.baudrate)
-                .timeout(Duration::from_millis(500))
-                .open()
-                .map_or_else(
-                    |e| {
-                        error!("No se pudo abrir el puerto `{}`: `{:?}`", port.port_name, e);
-                        self.error_modal = Some(AppError::connection(
-                            port.port_name.as_str(),
-                            &Error::new(e),
-                        ));
-                        None
-                    },
-                    Some,
+            self.load_serial_config(&port);
+            self.connect(port.port_name.clone());
+        }
+    }
+
+    /// Velocidad y parámetros de línea con los que se abre el puerto. Los
+    /// cambios se aplican en la próxima conexión.
+    fn show_serial_config(&mut self, ui: &mut Ui) {
+        let config = &mut self.serial_config;
+
+        egui::containers::ComboBox::from_label("Baudrate")
+            .selected_text(format!("{}", config.baudrate))
+            .show_ui(ui, |ui| {
+                for baudrate in BAUDRATES {
+                    ui.selectable_value(&mut config.baudrate, baudrate, format!("{baudrate}"));
+                }
+            });
+
+        ui.collapsing("Configuración avanzada", |ui| {
+            egui::Grid::new("serial_config")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Baudrate");
+                    ui.add(egui::DragValue::new(&mut config.baudrate).range(1..=4_000_000));
+                    ui.end_row();
+
+                    ui.label("Bits de datos");
+                    combo(
+                        ui,
+                        "data_bits",
+                        &mut config.data_bits,
+                        &[
+                            DataBits::Five,
+                            DataBits::Six,
+                            DataBits::Seven,
+                            DataBits::Eight,
+                        ],
+                    );
+                    ui.end_row();
+
+                    ui.label("Paridad");
+                    combo(
+                        ui,
+                        "parity",
+                        &mut config.parity,
+                        &[Parity::None, Parity::Odd, Parity::Even],
+                    );
+                    ui.end_row();
+
+                    ui.label("Bits de parada");
+                    combo(
+                        ui,
+                        "stop_bits",
+                        &mut config.stop_bits,
+                        &[StopBits::One, StopBits::Two],
+                    );
+                    ui.end_row();
+
+                    ui.label("Control de flujo");
+                    combo(
+                        ui,
+                        "flow_control",
+                        &mut config.flow_control,
+                        &[
+                            FlowControl::None,
+                            FlowControl::Software,
+                            FlowControl::Hardware,
+                        ],
+                    );
+                    ui.end_row();
+
+                    ui.label("Timeout");
+                    ui.add(
+                        egui::DragValue::new(&mut config.timeout_ms)
+                            .range(10..=10_000)
+                            .suffix(" ms"),
+                    );
+                    ui.end_row();
+
+                    ui.label("DTR al abrir");
+                    line_level(ui, "dtr_on_open", &mut config.dtr_on_open);
+                    ui.end_row();
+
+                    ui.label("RTS al abrir");
+                    line_level(ui, "rts_on_open", &mut config.rts_on_open);
+                    ui.end_row();
+
+                    ui.label("Heartbeat");
+                    ui.add(
+                        egui::DragValue::new(&mut config.heartbeat_ms)
+                            .range(0..=10_000)
+                            .suffix(" ms"),
+                    )
+                    .on_hover_text("Cero desactiva el heartbeat");
+                    ui.end_row();
+
+                    ui.label("Watchdog");
+                    ui.add(
+                        egui::DragValue::new(&mut config.watchdog_ms)
+                            .range(0..=60_000)
+                            .suffix(" ms"),
+                    )
+                    .on_hover_text(
+                        "Tiempo sin comandos tras el cual el dispositivo lleva el ciclo de trabajo a cero",
+                    );
+                    ui.end_row();
+                });
+
+            if config.watchdog_ms > 0 && config.watchdog_timeout().is_none() {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "⚠ El watchdog no se armará: el heartbeat debe estar activo y ser a lo sumo la mitad del watchdog",
                 );
+            }
+        });
+    }
+
+    /// Avisa al monitor que la interfaz se cierra. El proceso termina al volver
+    /// de `on_exit`, así que se espera a que el hilo auxiliar envíe el aviso.
+    fn stop_monitor(&self) {
+        if let Err(e) = self.tx.send(ThreadMessage::Stop) {
+            error!("Error en la comunicación con el hilo auxiliar: {e}");
+            return;
+        }
+
+        let deadline = Instant::now() + Self::MONITOR_STOP_TIMEOUT;
+        loop {
+            match self
+                .rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(ThreadMessage::Stopped) => return,
+                Ok(_) => {}
+                Err(_err) => {
+                    warn!("El hilo auxiliar no confirmó el aviso de cierre al monitor");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Lleva el ciclo de trabajo a cero y espera la confirmación del hilo
+    /// serial antes de permitir que la ventana se cierre.
+    fn shut_down(&self) {
+        debug!("Apagando el convertidor antes de salir");
+        self.send_serial(SerialRequest::Shutdown);
+
+        let deadline = Instant::now() + Self::SHUTDOWN_TIMEOUT;
+        loop {
+            match self
+                .serial_rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(SerialEvent::ShutdownFinished(Ok(()))) => return,
+                Ok(SerialEvent::ShutdownFinished(Err(e))) => {
+                    error!("No se pudo apagar el convertidor: {e}");
+                    return;
+                }
+                Ok(_) => {}
+                Err(_err) => {
+                    error!("El hilo serial no confirmó el apagado del convertidor");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Ejecuta un paso del lazo de voltaje con la última muestra del monitor.
+    fn run_regulator(&mut self) {
+        if self.serial_status != SerialStatus::Connected || self.stopped {
+            return;
+        }
+
+        let max_duty = self
+            .device_info
+            .as_ref()
+            .map(|info| info.capabilities)
+            .unwrap_or_default()
+            .max_duty;
+        let duty_cycle = {
+            let data = self.meas_data.borrow();
+            let Some(sample) = data.back() else {
+                return;
+            };
+            self.regulator.step(sample, self.duty_cycle.get(), max_duty)
+        };
+
+        if let Some(duty_cycle) = duty_cycle {
+            self.duty_cycle.set(duty_cycle);
+            self.duty_state = SettingState::Pending;
+            self.send_serial(SerialRequest::SetDuty(duty_cycle));
+        }
+    }
+
+    /// Ejecuta un paso del experimento de sintonía con la última muestra del
+    /// monitor.
+    fn run_autotune(&mut self) {
+        let Some(tuner) = self.autotuner.as_mut() else {
+            return;
+        };
+
+        let duty_cycle = tuner
+            .feed(self.meas_data.borrow().samples())
+            .or_else(|| tuner.expire(Instant::now()));
+
+        if let Some(outcome) = tuner.outcome() {
+            match outcome {
+                Ok(gains) => debug!("Sintonía terminada: {gains:?}"),
+                Err(error) => warn!("Sintonía fallida: {error}"),
+            }
+            self.autotune_result = Some(outcome.map_err(|e| e.to_string()));
+            self.autotuner = None;
+        }
+
+        if let Some(duty_cycle) = duty_cycle {
+            self.duty_cycle.set(duty_cycle);
+            self.duty_state = SettingState::Pending;
+            self.send_serial(SerialRequest::SetDuty(duty_cycle));
+        }
+    }
+
+    fn start_autotune(&mut self) {
+        let max_duty = self
+            .device_info
+            .as_ref()
+            .map(|info| info.capabilities)
+            .unwrap_or_default()
+            .max_duty;
+        debug!("Iniciando sintonía: {:?}", self.autotune_settings);
+
+        let (tuner, duty_cycle) = Autotuner::start(
+            self.autotune_settings,
+            self.duty_cycle.get(),
+            max_duty,
+            self.regulator.settings.setpoint,
+        );
+        self.autotuner = Some(tuner);
+        self.autotune_result = None;
+
+        if let Some(duty_cycle) = duty_cycle {
+            self.duty_cycle.set(duty_cycle);
+            self.duty_state = SettingState::Pending;
+            self.send_serial(SerialRequest::SetDuty(duty_cycle));
+        }
+    }
+
+    /// Interrumpe el experimento de sintonía y vuelve al ciclo de trabajo
+    /// con que se inició.
+    fn cancel_autotune(&mut self) {
+        let Some(tuner) = self.autotuner.take() else {
+            return;
+        };
+        if let Some(duty_cycle) = tuner.initial_duty() {
+            self.duty_cycle.set(duty_cycle);
+            self.duty_state = SettingState::Pending;
+            self.send_serial(SerialRequest::SetDuty(duty_cycle));
+        }
+    }
+
+    fn show_autotune(&mut self, ui: &mut Ui) {
+        let running = self.autotuner.is_some();
+        let settings = &mut self.autotune_settings;
+
+        ui.add_enabled_ui(!running, |ui| {
+            egui::Grid::new("autotune_settings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Método");
+                    egui::containers::ComboBox::from_id_salt("autotune_method")
+                        .selected_text(settings.method.label())
+                        .show_ui(ui, |ui| {
+                            for method in [AutotuneMethod::Relay, AutotuneMethod::Step] {
+                                ui.selectable_value(&mut settings.method, method, method.label());
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Amplitud");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.amplitude)
+                            .range(0.1..=50.0)
+                            .speed(0.1)
+                            .suffix(" %"),
+                    )
+                    .on_hover_text("Variación del ciclo de trabajo respecto del actual");
+                    ui.end_row();
+
+                    if settings.method == AutotuneMethod::Relay {
+                        ui.label("Histéresis");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.hysteresis)
+                                .range(0.0..=10.0)
+                                .speed(0.01)
+                                .suffix(" V"),
+                        );
+                        ui.end_row();
+
+                        ui.label("Ciclos");
+                        ui.add(egui::DragValue::new(&mut settings.cycles).range(2..=20));
+                        ui.end_row();
+                    }
+
+                    ui.label("Duración máxima");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.timeout_s)
+                            .range(1.0..=600.0)
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+                });
+        });
 
-            if let Some(port) = self.serial_port.as_mut() {
-                match attempt_handshake(port) {
-                    Ok((freq, duty)) => {
-                        self.frequency = freq.into();
-                        self.duty_cycle = duty.into();
+        if let Some(tuner) = &self.autotuner {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Experimento en curso: {:.1} s", tuner.elapsed()));
+            });
+            if ui.button("Cancelar").clicked() {
+                warn!("Sintonía cancelada, volviendo al ciclo de trabajo inicial");
+                self.cancel_autotune();
+            }
+            return;
+        }
+
+        let connected = self.serial_status == SerialStatus::Connected && !self.stopped;
+        if ui
+            .add_enabled(
+                connected && self.monitor_connected && !self.regulator.is_enabled(),
+                egui::Button::new("Iniciar"),
+            )
+            .on_disabled_hover_text(
+                "Requiere el dispositivo y el monitor conectados, y el lazo abierto",
+            )
+            .on_hover_text(
+                "Usa la referencia del lazo y el ciclo de trabajo actual como punto de operación",
+            )
+            .clicked()
+        {
+            self.start_autotune();
+        }
+
+        match self.autotune_result.clone() {
+            Some(Ok(gains)) => {
+                ui.label(format!(
+                    "Propuesta: Kp = {:.3}, Ki = {:.3}, Kd = {:.4}",
+                    gains.kp, gains.ki, gains.kd
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Aceptar").clicked() {
+                        self.regulator.settings.gains = gains;
+                        self.autotune_result = None;
                     }
-                    Err(e) => {
-                        error!("Falló el handshake con el dispositivo: {e:?}");
-                        self.error_modal = Some(AppError::handshake(
-                            port.name()
-                                .unwrap_or("puerto desconocido".to_owned())
-                                .as_str(),
-                            &e,
-                        ));
-                        self.serial_port = None;
+                    if ui.button("Descartar").clicked() {
+                        self.autotune_result = None;
                     }
-                }
+                });
+            }
+            Some(Err(error)) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
             }
+            None => {}
         }
     }
 
+    fn update_regulator_settings(&mut self, ui: &mut Ui) {
+        ui.collapsing("Regulación de voltaje", |ui| {
+            let connected = self.serial_status == SerialStatus::Connected && !self.stopped;
+            let mut enabled = self.regulator.is_enabled();
+            if ui
+                .add_enabled(
+                    connected && self.autotuner.is_none(),
+                    egui::Checkbox::new(&mut enabled, "Lazo cerrado"),
+                )
+                .changed()
+            {
+                if enabled {
+                    debug!("Lazo de voltaje habilitado");
+                    self.regulator.enable(self.duty_cycle.get());
+                } else {
+                    debug!("Lazo de voltaje deshabilitado");
+                    self.regulator.disable();
+                }
+            }
+
+            let settings = &mut self.regulator.settings;
+            egui::Grid::new("regulator_settings")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Referencia");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.setpoint)
+                            .range(0.0..=1000.0)
+                            .speed(0.1)
+                            .suffix(" V"),
+                    );
+                    ui.end_row();
+
+                    for (label, gain) in [
+                        ("Kp", &mut settings.gains.kp),
+                        ("Ki", &mut settings.gains.ki),
+                        ("Kd", &mut settings.gains.kd),
+                    ] {
+                        ui.label(label);
+                        ui.add(egui::DragValue::new(gain).range(0.0..=1000.0).speed(0.01));
+                        ui.end_row();
+                    }
+
+                    ui.label("Intervalo");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.interval_ms)
+                            .range(10..=10_000)
+                            .suffix(" ms"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Velocidad máxima");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.slew_rate)
+                            .range(0.0..=1000.0)
+                            .suffix(" %/s"),
+                    )
+                    .on_hover_text("Cero desactiva el límite");
+                    ui.end_row();
+                });
+
+            if self.regulator.is_enabled()
+                && let Some(output) = self.regulator.last_output()
+            {
+                ui.label(format!("Error: {:+.3} V", output.error));
+                ui.horizontal(|ui| {
+                    ui.label(format!("Salida del controlador: {:.2} %", output.output));
+                    if output.saturated {
+                        ui.colored_label(ui.visuals().warn_fg_color, "saturada");
+                    }
+                });
+            }
+
+            ui.collapsing("Auto-sintonía", |ui| self.show_autotune(ui));
+        });
+    }
+
+    fn update_ramp_settings(&mut self, ui: &mut Ui) {
+        let profile = &mut self.ramp_profile;
+        let frequency_ramp = &mut self.frequency_ramp;
+
+        ui.collapsing("Rampas", |ui| {
+            ui.label("Ciclo de trabajo");
+            egui::Grid::new("ramp_profile")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Umbral");
+                    ui.add(
+                        egui::DragValue::new(&mut profile.threshold)
+                            .range(0.0..=100.0)
+                            .speed(0.5)
+                            .suffix(" %"),
+                    )
+                    .on_hover_text("Los cambios menores se aplican de una vez");
+                    ui.end_row();
+
+                    ui.label("Duración");
+                    ui.add(
+                        egui::DragValue::new(&mut profile.duration_ms)
+                            .range(0..=60_000)
+                            .suffix(" ms"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Velocidad máxima");
+                    ui.add(
+                        egui::DragValue::new(&mut profile.slew_rate)
+                            .range(0.0..=1000.0)
+                            .suffix(" %/s"),
+                    )
+                    .on_hover_text("Cero desactiva el límite");
+                    ui.end_row();
+
+                    ui.label("Forma");
+                    egui::containers::ComboBox::from_id_salt("ramp_shape")
+                        .selected_text(profile.shape.label())
+                        .show_ui(ui, |ui| {
+                            for shape in [
+                                RampShape::Linear,
+                                RampShape::SCurve,
+                                RampShape::Stepped { steps: 5 },
+                            ] {
+                                let selected = std::mem::discriminant(&profile.shape)
+                                    == std::mem::discriminant(&shape);
+                                if ui.selectable_label(selected, shape.label()).clicked()
+                                    && !selected
+                                {
+                                    profile.shape = shape;
+                                }
+                            }
+                        });
+                    ui.end_row();
+
+                    if let RampShape::Stepped { steps } = &mut profile.shape {
+                        ui.label("Escalones");
+                        ui.add(egui::DragValue::new(steps).range(1..=100));
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            ui.label("Frecuencia");
+            egui::Grid::new("frequency_ramp")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Duración");
+                    ui.add(
+                        egui::DragValue::new(&mut frequency_ramp.duration_ms)
+                            .range(0..=60_000)
+                            .suffix(" ms"),
+                    )
+                    .on_hover_text("Cero aplica los cambios de una vez");
+                    ui.end_row();
+
+                    ui.label("Paso");
+                    ui.add(
+                        egui::DragValue::new(&mut frequency_ramp.step_hz)
+                            .range(1..=100_000)
+                            .suffix(" Hz"),
+                    )
+                    .on_hover_text("Solo si el firmware no ejecuta la rampa");
+                    ui.end_row();
+                });
+        });
+    }
+
     fn update_monitor_settings(&mut self, ui: &mut Ui) {
         ui.collapsing("Conexión a monitor", |ui| {
             let mut enter_pressed = false;
@@ -376,22 +1736,116 @@ impl SepicApp {
                     error!("Error en la comunicación con el hilo auxiliar: {e}");
                 });
                 self.monitor_connected = false;
+                if self.autotuner.is_some() {
+                    warn!("Monitor desconectado, sintonía cancelada");
+                    self.cancel_autotune();
+                }
+            }
+
+            if self.telemetry_received > 0 || self.telemetry_malformed > 0 {
+                let label = ui.label(format!(
+                    "Muestras: {}, datagramas descartados: {}",
+                    self.telemetry_received, self.telemetry_malformed
+                ));
+                if let Some(error) = &self.telemetry_error {
+                    label.on_hover_text(format!("Último error: {error}"));
+                }
             }
         });
     }
 }
 
+/// Valor guardado en una sesión anterior, si existe.
+fn stored<T: DeserializeOwned>(cc: &eframe::CreationContext<'_>, key: &str) -> Option<T> {
+    eframe::get_value(cc.storage?, key)
+}
+
+/// Selector entre un conjunto fijo de valores, mostrados con su `Display`.
+fn combo<T: PartialEq + Copy + fmt::Display>(ui: &mut Ui, id: &str, value: &mut T, options: &[T]) {
+    egui::containers::ComboBox::from_id_salt(id)
+        .selected_text(value.to_string())
+        .show_ui(ui, |ui| {
+            for &option in options {
+                ui.selectable_value(value, option, option.to_string());
+            }
+        });
+}
+
+fn line_level(ui: &mut Ui, id: &str, level: &mut Option<bool>) {
+    let text = |level: Option<bool>| match level {
+        None => "Sin cambios",
+        Some(true) => "Activo",
+        Some(false) => "Inactivo",
+    };
+
+    egui::containers::ComboBox::from_id_salt(id)
+        .selected_text(text(*level))
+        .show_ui(ui, |ui| {
+            for option in [None, Some(true), Some(false)] {
+                ui.selectable_value(level, option, text(option));
+            }
+        });
+}
+
 impl eframe::App for SepicApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::SERIAL_CONFIGS_KEY, &self.serial_configs);
+        eframe::set_value(storage, Self::RAMP_PROFILE_KEY, &self.ramp_profile);
+        eframe::set_value(storage, Self::INPUT_VOLTAGE_KEY, &self.manual_vin);
+        eframe::set_value(storage, Self::CONVERTER_KEY, &self.converter.get());
+        eframe::set_value(storage, Self::VIN_SOURCE_KEY, &self.vin_source);
+        eframe::set_value(storage, Self::FREQUENCY_RAMP_KEY, &self.frequency_ramp);
+        eframe::set_value(storage, Self::REGULATOR_KEY, &self.regulator.settings);
+        eframe::set_value(storage, Self::AUTOTUNE_KEY, &self.autotune_settings);
+        eframe::set_value(storage, Self::LEAVE_RUNNING_KEY, &self.leave_running);
+        eframe::set_value(storage, Self::STOP_MONITOR_KEY, &self.stop_monitor_on_exit);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.monitor_connected && self.stop_monitor_on_exit {
+            self.stop_monitor();
+        }
+
+        if matches!(
+            self.serial_status,
+            SerialStatus::Disconnected | SerialStatus::Probing
+        ) {
+            return;
+        }
+
+        if self.leave_running {
+            warn!("Saliendo con el convertidor encendido");
+            self.send_serial(SerialRequest::Detach);
+        } else {
+            self.shut_down();
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.poll_messages().unwrap_or_else(|e| {
             error!("Error al hacer polling a los mensajes del hilo auxiliar: {e}");
         });
 
-        if self.serial_port.is_none() {
+        self.poll_serial_events();
+        self.poll_input_voltage();
+        self.vin.set(self.input_voltage());
+        self.run_regulator();
+        self.run_autotune();
+
+        if ctx.input_mut(|i| i.consume_shortcut(&Self::STOP_SHORTCUT)) && self.can_stop() {
+            self.emergency_stop();
+        }
+
+        if self.serial_status == SerialStatus::Disconnected {
             self.port_info = None;
         }
+        if self.serial_status != SerialStatus::Disconnected || self.monitor_connected {
+            // Los eventos del hilo serial y las muestras del monitor llegan
+            // sin interacción del usuario.
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
 
-        Self::update_menubar(ctx, _frame);
+        self.update_menubar(ctx, _frame);
         self.update_settingsbar(ctx, _frame);
 
         let mut viewer = MyTabViewer::new();
@@ -446,10 +1900,38 @@ impl AppError {
         }
     }
 
+    pub fn stop(error: &Error) -> Self {
+        Self {
+            description: "No se pudo detener el convertidor".to_owned(),
+            source_description: error.to_string(),
+        }
+    }
+
     pub fn setting(var: &str, error: &Error) -> Self {
+        if let Some(rejection) = error.downcast_ref::<DeviceError>() {
+            return Self::rejected(var, rejection);
+        }
+
         Self {
             description: format!("Ocurrió un problema al ajustar {var}"),
             source_description: error.to_string(),
         }
     }
+
+    pub fn readback(error: &Error) -> Self {
+        Self {
+            description: "No se pudieron leer los valores del dispositivo".to_owned(),
+            source_description: error.to_string(),
+        }
+    }
+
+    pub fn rejected(var: &str, error: &DeviceError) -> Self {
+        Self {
+            description: match error.code {
+                Some(code) => format!("El dispositivo rechazó el ajuste de {var}: {code}"),
+                None => format!("El dispositivo rechazó el ajuste de {var}"),
+            },
+            source_description: error.to_string(),
+        }
+    }
 }
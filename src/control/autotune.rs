@@ -0,0 +1,537 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::PidGains;
+use crate::{
+    serialcomms::DutyQ9,
+    tabs::{Channel, Measurement},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutotuneMethod {
+    /// Oscilación sostenida con un relé alrededor de la referencia.
+    Relay,
+    /// Escalón en lazo abierto ajustado a un modelo de primer orden con
+    /// retardo.
+    Step,
+}
+
+impl AutotuneMethod {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Relay => "Relé",
+            Self::Step => "Escalón",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutotuneSettings {
+    pub method: AutotuneMethod,
+    /// Amplitud del relé o del escalón, en % de ciclo de trabajo.
+    pub amplitude: f32,
+    /// Histéresis del relé, en V.
+    pub hysteresis: f32,
+    /// Ciclos completos del relé que se promedian.
+    pub cycles: u32,
+    /// Duración máxima del experimento, en s. El escalón dura exactamente
+    /// este tiempo.
+    pub timeout_s: f32,
+}
+
+impl AutotuneSettings {
+    /// Margen sobre `timeout_s` antes de abortar por reloj, para tolerar el
+    /// retardo de las muestras del monitor.
+    const DEADLINE_MARGIN: Duration = Duration::from_secs(5);
+
+    /// Tiempo de reloj tras el cual se aborta el experimento aunque el
+    /// monitor haya dejado de enviar muestras.
+    fn deadline(&self) -> Duration {
+        Duration::try_from_secs_f32(self.timeout_s)
+            .unwrap_or_default()
+            .saturating_add(Self::DEADLINE_MARGIN)
+    }
+}
+
+impl Default for AutotuneSettings {
+    fn default() -> Self {
+        Self {
+            method: AutotuneMethod::Relay,
+            amplitude: 5.0,
+            hysteresis: 0.1,
+            cycles: 4,
+            timeout_s: 30.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum AutotuneError {
+    #[error("El experimento no terminó en el tiempo máximo")]
+    Timeout,
+    #[error("La salida no oscila con la amplitud de relé elegida")]
+    NoOscillation,
+    #[error("La salida no respondió al escalón")]
+    NoResponse,
+}
+
+/// Estado del relé: conmutaciones y extremos de la salida en cada ciclo.
+#[derive(Default)]
+struct Relay {
+    high: bool,
+    rising_edges: Vec<f32>,
+    max: f32,
+    min: f32,
+    amplitudes: Vec<f32>,
+}
+
+/// Experimento de sintonía en curso. Recibe las muestras del monitor y
+/// decide el ciclo de trabajo a aplicar en cada momento.
+pub struct Autotuner {
+    settings: AutotuneSettings,
+    /// Ciclo de trabajo al iniciar, al que se vuelve al terminar, en %.
+    bias: f32,
+    max_duty: f32,
+    /// Escalón efectivamente aplicado, en %, tras recortar al máximo del
+    /// dispositivo.
+    step: f32,
+    /// Instante de reloj en que se aborta el experimento; `None` si el plazo
+    /// no es representable.
+    deadline: Option<Instant>,
+    /// Referencia del relé, en V.
+    setpoint: f32,
+    start: Option<DateTime<Local>>,
+    last_sample: Option<DateTime<Local>>,
+    samples: Vec<(f32, f32)>,
+    relay: Relay,
+    outcome: Option<Result<PidGains, AutotuneError>>,
+}
+
+impl Autotuner {
+    /// Prepara el experimento y devuelve el primer ciclo de trabajo a
+    /// aplicar. El relé parte en alto, de modo que la salida comienza
+    /// subiendo hacia `setpoint`.
+    pub fn start(
+        settings: AutotuneSettings,
+        duty_cycle: DutyQ9,
+        max_duty: DutyQ9,
+        setpoint: f32,
+    ) -> (Self, Option<DutyQ9>) {
+        let mut tuner = Self {
+            settings,
+            bias: duty_cycle.percent(),
+            max_duty: max_duty.percent(),
+            step: 0.0,
+            deadline: Instant::now().checked_add(settings.deadline()),
+            setpoint,
+            start: None,
+            last_sample: None,
+            samples: Vec::new(),
+            relay: Relay {
+                high: true,
+                max: f32::MIN,
+                min: f32::MAX,
+                ..Relay::default()
+            },
+            outcome: None,
+        };
+        let duty = tuner.duty(tuner.bias + settings.amplitude);
+        let bias = tuner.initial_duty().map_or(0.0, DutyQ9::percent);
+        tuner.step = duty.map_or(0.0, DutyQ9::percent) - bias;
+        (tuner, duty)
+    }
+
+    pub fn outcome(&self) -> Option<Result<PidGains, AutotuneError>> {
+        self.outcome
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.samples.last().map_or(0.0, |&(t, _)| t)
+    }
+
+    fn duty(&self, percent: f32) -> Option<DutyQ9> {
+        DutyQ9::from_percent(percent.clamp(0.0, self.max_duty)).ok()
+    }
+
+    /// Ciclo de trabajo al iniciar el experimento, al que se vuelve al
+    /// terminar o cancelar.
+    pub fn initial_duty(&self) -> Option<DutyQ9> {
+        self.duty(self.bias)
+    }
+
+    fn finish(&mut self, outcome: Result<PidGains, AutotuneError>) -> Option<DutyQ9> {
+        self.outcome = Some(outcome);
+        self.initial_duty()
+    }
+
+    /// Aborta el experimento si pasó el plazo de reloj, aunque no lleguen
+    /// muestras. Devuelve entonces el ciclo original.
+    pub fn expire(&mut self, now: Instant) -> Option<DutyQ9> {
+        if self.outcome.is_some() || self.deadline.is_none_or(|deadline| now < deadline) {
+            return None;
+        }
+        self.finish(Err(AutotuneError::Timeout))
+    }
+
+    /// Procesa en orden todas las muestras posteriores a la última vista; al
+    /// iniciar, solo la más reciente. Devuelve el último ciclo de trabajo
+    /// decidido.
+    pub fn feed(&mut self, data: &VecDeque<Measurement>) -> Option<DutyQ9> {
+        let start = match self.last_sample {
+            Some(last) => data.partition_point(|sample| sample.timestamp <= last),
+            None => data.len().saturating_sub(1),
+        };
+
+        let mut duty_cycle = None;
+        for sample in data.range(start..) {
+            duty_cycle = self.step(sample).or(duty_cycle);
+            if self.outcome.is_some() {
+                break;
+            }
+        }
+        duty_cycle
+    }
+
+    /// Procesa el canal `Vout` de la muestra si es nueva. Devuelve el ciclo de trabajo a
+    /// aplicar cuando cambia; al terminar, el ciclo original.
+    fn step(&mut self, sample: &Measurement) -> Option<DutyQ9> {
+        if self.outcome.is_some() || self.last_sample.is_some_and(|t| sample.timestamp <= t) {
+            return None;
+        }
+        let value = sample.get(Channel::Vout)? as f32;
+        self.last_sample = Some(sample.timestamp);
+
+        let start = *self.start.get_or_insert(sample.timestamp);
+        let t = (sample.timestamp - start).num_microseconds()? as f32 / 1e6;
+        self.samples.push((t, value));
+
+        match self.settings.method {
+            AutotuneMethod::Relay => self.relay_step(t, value),
+            AutotuneMethod::Step if t >= self.settings.timeout_s => {
+                let outcome = self.fit_step();
+                self.finish(outcome)
+            }
+            AutotuneMethod::Step => None,
+        }
+    }
+
+    fn relay_step(&mut self, t: f32, value: f32) -> Option<DutyQ9> {
+        if t >= self.settings.timeout_s {
+            return self.finish(Err(AutotuneError::Timeout));
+        }
+
+        let relay = &mut self.relay;
+        relay.max = relay.max.max(value);
+        relay.min = relay.min.min(value);
+
+        let eps = self.settings.hysteresis;
+        let switch_low = relay.high && value > self.setpoint + eps;
+        let switch_high = !relay.high && value < self.setpoint - eps;
+        if !switch_low && !switch_high {
+            return None;
+        }
+
+        relay.high = switch_high;
+        if switch_high {
+            // Un ciclo completo termina en cada flanco de subida; el primero
+            // se descarta porque parte desde el reposo.
+            if !relay.rising_edges.is_empty() {
+                relay.amplitudes.push((relay.max - relay.min) / 2.0);
+            }
+            relay.rising_edges.push(t);
+            relay.max = f32::MIN;
+            relay.min = f32::MAX;
+        }
+
+        if relay.amplitudes.len() > self.settings.cycles as usize {
+            let outcome = self.fit_relay();
+            return self.finish(outcome);
+        }
+
+        let output = if self.relay.high {
+            self.bias + self.settings.amplitude
+        } else {
+            self.bias - self.settings.amplitude
+        };
+        self.duty(output)
+    }
+
+    /// Ganancia y período últimos a partir de la oscilación del relé, con
+    /// `Ku = 4h / (π·√(a² − ε²))`, y ganancias de Ziegler-Nichols.
+    fn fit_relay(&self) -> Result<PidGains, AutotuneError> {
+        let relay = &self.relay;
+        let amplitudes = relay.amplitudes.get(1..).unwrap_or_default();
+        let periods: Vec<f32> = relay
+            .rising_edges
+            .windows(2)
+            .skip(1)
+            .filter_map(|edges| match edges {
+                [a, b] => Some(b - a),
+                _ => None,
+            })
+            .collect();
+        if amplitudes.is_empty() || periods.is_empty() {
+            return Err(AutotuneError::NoOscillation);
+        }
+
+        let amplitude = amplitudes.iter().sum::<f32>() / amplitudes.len() as f32;
+        let period = periods.iter().sum::<f32>() / periods.len() as f32;
+        let eps = self.settings.hysteresis;
+        if amplitude <= eps {
+            return Err(AutotuneError::NoOscillation);
+        }
+
+        let ku = 4.0 * self.settings.amplitude / (PI * (amplitude * amplitude - eps * eps).sqrt());
+        let kp = 0.6 * ku;
+        Ok(PidGains {
+            kp,
+            ki: kp / (period / 2.0),
+            kd: kp * period / 8.0,
+        })
+    }
+
+    /// Ajusta un modelo de primer orden con retardo por el método de dos
+    /// puntos (28,3 % y 63,2 % de la respuesta) y aplica la curva de reacción
+    /// de Ziegler-Nichols.
+    fn fit_step(&self) -> Result<PidGains, AutotuneError> {
+        let &(_, initial) = self.samples.first().ok_or(AutotuneError::NoResponse)?;
+        // La salida final se promedia en el último décimo del experimento.
+        let tail = self
+            .samples
+            .get(self.samples.len() * 9 / 10..)
+            .unwrap_or_default();
+        let last = tail.iter().map(|&(_, y)| y).sum::<f32>() / tail.len() as f32;
+        let delta = last - initial;
+        if delta.abs() <= f32::EPSILON || self.step <= 0.0 {
+            return Err(AutotuneError::NoResponse);
+        }
+
+        let crossing = |fraction: f32| {
+            let level = initial + delta * fraction;
+            self.samples
+                .iter()
+                .find(|&&(_, y)| (y - level) * delta.signum() >= 0.0)
+                .map(|&(t, _)| t)
+        };
+        let (Some(t28), Some(t63)) = (crossing(0.283), crossing(0.632)) else {
+            return Err(AutotuneError::NoResponse);
+        };
+
+        let gain = delta / self.step;
+        let tau = 1.5 * (t63 - t28);
+        // Con retardo nulo las reglas de Ziegler-Nichols divergen; se usa
+        // como mínimo el período de muestreo.
+        let sample_period = self.elapsed() / self.samples.len() as f32;
+        let theta = (t63 - tau).max(sample_period);
+        if gain <= 0.0 || tau <= 0.0 {
+            return Err(AutotuneError::NoResponse);
+        }
+
+        let kp = 1.2 * tau / (gain * theta);
+        Ok(PidGains {
+            kp,
+            ki: kp / (2.0 * theta),
+            kd: kp * 0.5 * theta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        f32::consts::PI,
+        time::{Duration, Instant},
+    };
+
+    use chrono::{Local, TimeDelta};
+
+    use super::{AutotuneError, AutotuneMethod, AutotuneSettings, Autotuner};
+    use crate::{
+        control::PidGains,
+        serialcomms::DutyQ9,
+        tabs::{Channel, Channels, Measurement},
+    };
+
+    const PERIOD_MS: i64 = 10;
+
+    fn percent(percent: f32) -> DutyQ9 {
+        DutyQ9::from_percent(percent).expect("porcentaje válido")
+    }
+
+    /// Muestras de `Vout` cada `PERIOD_MS` durante `duration_s`.
+    fn samples(duration_s: f32, vout: impl Fn(f32) -> f32) -> VecDeque<Measurement> {
+        let start = Local::now();
+        let count = (duration_s * 1000.0) as i64 / PERIOD_MS;
+        (0..=count)
+            .map(|i| {
+                let t = (i * PERIOD_MS) as f32 / 1000.0;
+                let mut channels = Channels::default();
+                channels.insert(Channel::Vout, f64::from(vout(t)));
+                Measurement {
+                    timestamp: start + TimeDelta::milliseconds(i * PERIOD_MS),
+                    channels,
+                }
+            })
+            .collect()
+    }
+
+    /// Procesa las muestras una a una, como llegarían del monitor.
+    fn run(tuner: &mut Autotuner, data: VecDeque<Measurement>) -> Option<DutyQ9> {
+        let mut fed = VecDeque::new();
+        let mut duty_cycle = None;
+        for sample in data {
+            fed.push_back(sample);
+            duty_cycle = tuner.feed(&fed).or(duty_cycle);
+        }
+        duty_cycle
+    }
+
+    fn gains(tuner: &Autotuner) -> PidGains {
+        tuner
+            .outcome()
+            .expect("experimento terminado")
+            .expect("ganancias ajustadas")
+    }
+
+    fn assert_close(actual: f32, expected: f32, what: &str) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 0.05,
+            "{what}: {actual} en vez de {expected}"
+        );
+    }
+
+    fn step_settings() -> AutotuneSettings {
+        AutotuneSettings {
+            method: AutotuneMethod::Step,
+            amplitude: 5.0,
+            timeout_s: 10.0,
+            ..AutotuneSettings::default()
+        }
+    }
+
+    /// Respuesta de un modelo de primer orden con retardo a un escalón de
+    /// `step` % de ciclo de trabajo.
+    fn first_order(gain: f32, tau: f32, theta: f32, step: f32) -> impl Fn(f32) -> f32 {
+        move |t| {
+            let response = if t < theta {
+                0.0
+            } else {
+                1.0 - (-(t - theta) / tau).exp()
+            };
+            10.0 + gain * step * response
+        }
+    }
+
+    #[test]
+    fn step_fits_first_order_model() {
+        let (mut tuner, duty_cycle) =
+            Autotuner::start(step_settings(), percent(50.0), DutyQ9::MAX, 12.0);
+        assert_eq!(duty_cycle, Some(percent(55.0)), "escalón aplicado");
+
+        let restored = run(&mut tuner, samples(10.0, first_order(2.0, 1.0, 0.5, 5.0)));
+        assert_eq!(restored, Some(percent(50.0)), "vuelve al ciclo inicial");
+
+        // Kp = 1,2·τ / (K·θ), Ti = 2θ, Td = θ/2.
+        let gains = gains(&tuner);
+        assert_close(gains.kp, 1.2, "Kp");
+        assert_close(gains.ki, 1.2, "Ki");
+        assert_close(gains.kd, 0.3, "Kd");
+    }
+
+    #[test]
+    fn step_uses_the_applied_amplitude() {
+        let (mut tuner, duty_cycle) =
+            Autotuner::start(step_settings(), percent(70.0), percent(72.0), 12.0);
+        assert_eq!(duty_cycle, Some(percent(72.0)), "escalón recortado");
+
+        run(&mut tuner, samples(10.0, first_order(2.0, 1.0, 0.5, 2.0)));
+        assert_close(
+            gains(&tuner).kp,
+            1.2,
+            "Kp con la ganancia real de la planta",
+        );
+    }
+
+    #[test]
+    fn step_without_response_fails() {
+        let (mut tuner, _) = Autotuner::start(step_settings(), percent(50.0), DutyQ9::MAX, 12.0);
+        run(&mut tuner, samples(10.0, |_| 10.0));
+        assert_eq!(
+            tuner.outcome(),
+            Some(Err(AutotuneError::NoResponse)),
+            "salida constante"
+        );
+    }
+
+    fn relay_settings() -> AutotuneSettings {
+        AutotuneSettings {
+            method: AutotuneMethod::Relay,
+            amplitude: 5.0,
+            hysteresis: 0.1,
+            cycles: 4,
+            timeout_s: 30.0,
+        }
+    }
+
+    #[test]
+    fn relay_fits_ultimate_gain_and_period() {
+        let (mut tuner, duty_cycle) =
+            Autotuner::start(relay_settings(), percent(50.0), DutyQ9::MAX, 12.0);
+        assert_eq!(duty_cycle, Some(percent(55.0)), "el relé parte en alto");
+
+        // Oscilación de 1 V de amplitud y 2 s de período alrededor de la
+        // referencia.
+        let restored = run(
+            &mut tuner,
+            samples(30.0, |t| 12.0 + (2.0 * PI * t / 2.0).sin()),
+        );
+        assert_eq!(restored, Some(percent(50.0)), "vuelve al ciclo inicial");
+
+        let ku = 4.0 * 5.0 / (PI * (1.0_f32 - 0.01).sqrt());
+        let gains = gains(&tuner);
+        assert_close(gains.kp, 0.6 * ku, "Kp");
+        assert_close(gains.ki, 0.6 * ku, "Ki");
+        assert_close(gains.kd, 0.6 * ku * 2.0 / 8.0, "Kd");
+    }
+
+    #[test]
+    fn relay_without_oscillation_times_out() {
+        let (mut tuner, _) = Autotuner::start(relay_settings(), percent(50.0), DutyQ9::MAX, 12.0);
+        let restored = run(&mut tuner, samples(31.0, |_| 10.0));
+        assert_eq!(restored, Some(percent(50.0)), "vuelve al ciclo inicial");
+        assert_eq!(
+            tuner.outcome(),
+            Some(Err(AutotuneError::Timeout)),
+            "sin oscilación"
+        );
+    }
+
+    #[test]
+    fn deadline_aborts_without_samples() {
+        let (mut tuner, _) = Autotuner::start(relay_settings(), percent(50.0), DutyQ9::MAX, 12.0);
+        assert_eq!(tuner.expire(Instant::now()), None, "dentro del plazo");
+        assert_eq!(tuner.outcome(), None, "sigue en curso");
+
+        let later = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            tuner.expire(later),
+            Some(percent(50.0)),
+            "vuelve al ciclo inicial"
+        );
+        assert_eq!(
+            tuner.outcome(),
+            Some(Err(AutotuneError::Timeout)),
+            "plazo vencido"
+        );
+        assert_eq!(tuner.expire(later), None, "solo termina una vez");
+    }
+}
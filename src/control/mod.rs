@@ -0,0 +1,398 @@
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    serialcomms::DutyQ9,
+    tabs::{Channel, Measurement},
+};
+
+mod autotune;
+pub use autotune::{AutotuneMethod, AutotuneSettings, Autotuner};
+
+/// Ganancias del PID, con el error en V y la salida en % de ciclo de trabajo.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PidGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+impl Default for PidGains {
+    fn default() -> Self {
+        Self {
+            kp: 1.0,
+            ki: 5.0,
+            kd: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidOutput {
+    pub error: f32,
+    pub output: f32,
+    /// La salida quedó recortada por los límites.
+    pub saturated: bool,
+}
+
+/// PID con derivada sobre la medición y anti-windup por integración
+/// condicional.
+#[derive(Clone, Debug, Default)]
+pub struct Pid {
+    pub gains: PidGains,
+    integral: f32,
+    prev_measurement: Option<f32>,
+}
+
+impl Pid {
+    /// Reinicia el controlador de modo que su próxima salida parta de
+    /// `output`, evitando un salto al habilitarlo.
+    pub fn reset(&mut self, output: f32) {
+        self.integral = output;
+        self.prev_measurement = None;
+    }
+
+    pub fn update(
+        &mut self,
+        setpoint: f32,
+        measurement: f32,
+        dt: f32,
+        limits: RangeInclusive<f32>,
+    ) -> PidOutput {
+        let (min, max) = (*limits.start(), *limits.end());
+        let error = setpoint - measurement;
+
+        let derivative = match self.prev_measurement {
+            Some(prev) if dt > 0.0 => -(measurement - prev) / dt,
+            _ => 0.0,
+        };
+        self.prev_measurement = Some(measurement);
+
+        let integral = self.integral + self.gains.ki * error * dt;
+        let unclamped = self.gains.kp * error + integral + self.gains.kd * derivative;
+        let output = unclamped.clamp(min, max);
+        let saturated = output != unclamped;
+
+        // La integral solo avanza si no empuja la salida más allá del límite.
+        if !saturated || (unclamped > max && error < 0.0) || (unclamped < min && error > 0.0) {
+            self.integral = integral.clamp(min, max);
+        }
+
+        PidOutput {
+            error,
+            output,
+            saturated,
+        }
+    }
+}
+
+/// Configuración del lazo de regulación de voltaje.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegulatorSettings {
+    /// Voltaje de salida deseado, en V.
+    pub setpoint: f32,
+    pub gains: PidGains,
+    /// Intervalo mínimo entre comandos al dispositivo, en ms.
+    pub interval_ms: u32,
+    /// Variación máxima del ciclo de trabajo, en %/s; cero la deja sin límite.
+    pub slew_rate: f32,
+}
+
+impl Default for RegulatorSettings {
+    fn default() -> Self {
+        Self {
+            setpoint: 12.0,
+            gains: PidGains::default(),
+            interval_ms: 100,
+            slew_rate: 20.0,
+        }
+    }
+}
+
+/// Lazo cerrado de voltaje ejecutado en la interfaz: toma el canal `Vout` de
+/// la última muestra del monitor y propone el ciclo de trabajo a enviar.
+#[derive(Default)]
+pub struct Regulator {
+    pub settings: RegulatorSettings,
+    enabled: bool,
+    pid: Pid,
+    last_sample: Option<DateTime<Local>>,
+    last_update: Option<Instant>,
+    last_output: Option<PidOutput>,
+}
+
+impl Regulator {
+    pub fn new(settings: RegulatorSettings) -> Self {
+        Self {
+            settings,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Habilita el lazo partiendo del ciclo de trabajo actual.
+    pub fn enable(&mut self, duty_cycle: DutyQ9) {
+        self.enabled = true;
+        self.pid.reset(duty_cycle.percent());
+        self.last_sample = None;
+        self.last_update = None;
+        self.last_output = None;
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    pub fn last_output(&self) -> Option<PidOutput> {
+        self.last_output
+    }
+
+    /// Procesa la muestra si es nueva y ya pasó el intervalo entre comandos.
+    /// Devuelve el ciclo de trabajo a aplicar si difiere del actual.
+    pub fn step(
+        &mut self,
+        sample: &Measurement,
+        duty_cycle: DutyQ9,
+        max_duty: DutyQ9,
+    ) -> Option<DutyQ9> {
+        if !self.enabled {
+            return None;
+        }
+
+        let now = Instant::now();
+        let interval = Duration::from_millis(u64::from(self.settings.interval_ms));
+        if self.last_update.is_some_and(|last| now < last + interval) {
+            return None;
+        }
+
+        let measurement = sample.get(Channel::Vout)? as f32;
+        let prev_sample = self.last_sample;
+        if prev_sample.is_some_and(|prev| sample.timestamp <= prev) {
+            return None;
+        }
+        self.last_sample = Some(sample.timestamp);
+        let elapsed = self.last_update.map(|last| now - last);
+        self.last_update = Some(now);
+
+        // La primera muestra solo sirve de referencia para medir `dt`.
+        let dt = (sample.timestamp - prev_sample?).num_microseconds()? as f32 / 1e6;
+
+        self.pid.gains = self.settings.gains;
+        let result = self.pid.update(
+            self.settings.setpoint,
+            measurement,
+            dt,
+            0.0..=max_duty.percent(),
+        );
+        self.last_output = Some(result);
+
+        let current = duty_cycle.percent();
+        let output = match elapsed {
+            Some(elapsed) if self.settings.slew_rate > 0.0 => {
+                let max_step = self.settings.slew_rate * elapsed.as_secs_f32();
+                result.output.clamp(current - max_step, current + max_step)
+            }
+            _ => result.output,
+        };
+
+        DutyQ9::from_percent(output)
+            .ok()
+            .filter(|&duty| duty != duty_cycle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Local, TimeDelta};
+
+    use super::{Pid, PidGains, Regulator, RegulatorSettings};
+    use crate::{
+        serialcomms::DutyQ9,
+        tabs::{Channel, Channels, Measurement},
+    };
+
+    fn pid(kp: f32, ki: f32, kd: f32) -> Pid {
+        Pid {
+            gains: PidGains { kp, ki, kd },
+            ..Pid::default()
+        }
+    }
+
+    fn percent(percent: f32) -> DutyQ9 {
+        DutyQ9::from_percent(percent).expect("porcentaje válido")
+    }
+
+    fn sample(start: DateTime<Local>, ms: i64, vout: f64) -> Measurement {
+        let mut channels = Channels::default();
+        channels.insert(Channel::Vout, vout);
+        Measurement {
+            timestamp: start + TimeDelta::milliseconds(ms),
+            channels,
+        }
+    }
+
+    /// Regulador proporcional, sin intervalo mínimo ni límite de velocidad.
+    fn proportional(slew_rate: f32) -> Regulator {
+        let mut regulator = Regulator::new(RegulatorSettings {
+            setpoint: 12.0,
+            gains: PidGains {
+                kp: 1.0,
+                ki: 0.0,
+                kd: 0.0,
+            },
+            interval_ms: 0,
+            slew_rate,
+        });
+        regulator.enable(percent(50.0));
+        regulator
+    }
+
+    #[test]
+    fn pid_clamps_its_output() {
+        let mut pid = pid(2.0, 0.0, 0.0);
+        let result = pid.update(10.0, 8.0, 0.1, 0.0..=100.0);
+        assert_eq!(
+            (result.error, result.output, result.saturated),
+            (2.0, 4.0, false),
+            "proporcional"
+        );
+
+        let result = pid.update(100.0, 0.0, 0.1, 0.0..=75.0);
+        assert_eq!(result.output, 75.0, "límite superior");
+        assert!(result.saturated, "saturado arriba");
+
+        let result = pid.update(0.0, 100.0, 0.1, 0.0..=75.0);
+        assert_eq!(result.output, 0.0, "límite inferior");
+        assert!(result.saturated, "saturado abajo");
+    }
+
+    #[test]
+    fn pid_integral_does_not_wind_up() {
+        let mut pid = pid(0.0, 10.0, 0.0);
+        for _ in 0..1000 {
+            pid.update(20.0, 10.0, 0.1, 0.0..=50.0);
+        }
+        let result = pid.update(20.0, 10.0, 0.1, 0.0..=50.0);
+        assert!(result.saturated, "saturado por la integral");
+
+        let result = pid.update(10.0, 11.0, 0.1, 0.0..=50.0);
+        assert!(
+            result.output < 50.0 && !result.saturated,
+            "la salida baja apenas se invierte el error: {result:?}"
+        );
+    }
+
+    #[test]
+    fn pid_differentiates_the_measurement() {
+        let mut pid = pid(0.0, 0.0, 1.0);
+        assert_eq!(
+            pid.update(12.0, 10.0, 0.1, -100.0..=100.0).output,
+            0.0,
+            "sin medición previa no hay derivada"
+        );
+        let output = pid.update(12.0, 11.0, 0.1, -100.0..=100.0).output;
+        assert!((output + 10.0).abs() < 1e-4, "1 V en 0.1 s: {output}");
+        assert_eq!(
+            pid.update(20.0, 11.0, 0.1, -100.0..=100.0).output,
+            0.0,
+            "un cambio de setpoint no produce un salto derivativo"
+        );
+    }
+
+    #[test]
+    fn pid_reset_starts_from_the_given_output() {
+        let mut pid = pid(1.0, 1.0, 0.0);
+        pid.reset(30.0);
+        assert_eq!(
+            pid.update(12.0, 12.0, 0.1, 0.0..=100.0).output,
+            30.0,
+            "sin error la salida se mantiene"
+        );
+    }
+
+    #[test]
+    fn regulator_uses_each_sample_once() {
+        let start = Local::now();
+        let mut regulator = proportional(0.0);
+
+        assert_eq!(
+            regulator.step(&sample(start, 0, 10.0), percent(50.0), DutyQ9::MAX),
+            None,
+            "la primera muestra solo sirve de referencia"
+        );
+        let second = sample(start, 10, 10.0);
+        assert_eq!(
+            regulator.step(&second, percent(50.0), DutyQ9::MAX),
+            Some(percent(52.0)),
+            "2 V de error con Kp = 1"
+        );
+        assert_eq!(
+            regulator.step(&second, percent(52.0), DutyQ9::MAX),
+            None,
+            "una muestra repetida no se procesa"
+        );
+        assert_eq!(
+            regulator.step(&sample(start, 5, 0.0), percent(52.0), DutyQ9::MAX),
+            None,
+            "una muestra anterior no se procesa"
+        );
+    }
+
+    #[test]
+    fn regulator_respects_max_duty() {
+        let start = Local::now();
+        let mut regulator = proportional(0.0);
+        regulator.step(&sample(start, 0, 0.0), percent(50.0), percent(75.0));
+        assert_eq!(
+            regulator.step(&sample(start, 10, 0.0), percent(50.0), percent(75.0)),
+            Some(percent(62.0)),
+            "12 V de error"
+        );
+
+        let mut regulator = proportional(0.0);
+        regulator.settings.gains.kp = 10.0;
+        regulator.step(&sample(start, 0, 0.0), percent(50.0), percent(75.0));
+        assert_eq!(
+            regulator.step(&sample(start, 10, 0.0), percent(50.0), percent(75.0)),
+            Some(percent(75.0)),
+            "recortado al máximo del dispositivo"
+        );
+    }
+
+    #[test]
+    fn regulator_limits_slew_rate() {
+        let start = Local::now();
+        let mut regulator = proportional(0.001);
+        let current = percent(50.0);
+        regulator.step(&sample(start, 0, 0.0), current, DutyQ9::MAX);
+        let duty = regulator.step(&sample(start, 10, 0.0), current, DutyQ9::MAX);
+        assert!(
+            duty.is_none_or(|duty| duty.raw().abs_diff(current.raw()) <= 1),
+            "a 0.001 %/s el ciclo casi no cambia: {duty:?}"
+        );
+    }
+
+    #[test]
+    fn disabled_regulator_does_nothing() {
+        let start = Local::now();
+        let mut regulator = proportional(0.0);
+        regulator.disable();
+        regulator.step(&sample(start, 0, 0.0), percent(50.0), DutyQ9::MAX);
+        assert_eq!(
+            regulator.step(&sample(start, 10, 0.0), percent(50.0), DutyQ9::MAX),
+            None,
+            "deshabilitado"
+        );
+        assert!(regulator.last_output().is_none(), "sin cálculo");
+    }
+}
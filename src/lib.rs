@@ -3,9 +3,17 @@
 mod app;
 pub use app::SepicApp;
 
+mod control;
+
+mod model;
+
+mod ramp;
+
 mod serialcomms;
 
 mod tabs;
 pub use tabs::MyTabViewer;
 
+mod telemetry;
+
 pub mod threading;
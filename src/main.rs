@@ -2,10 +2,15 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 use log::error;
-use std::sync::{mpsc, mpsc::Receiver, mpsc::Sender};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, mpsc::Receiver, mpsc::Sender},
+};
 use tokio::runtime::Runtime;
 
-use sepic_gui::threading::{MessagingThread, ThreadMessage};
+use sepic_gui::threading::{
+    MessagingThread, SerialEvent, SerialRequest, SerialThread, ThreadMessage,
+};
 
 fn main() -> eframe::Result {
     let rt = Runtime::new().expect("No se pudo crear el Runtime para Tokio");
@@ -29,6 +34,14 @@ fn main() -> eframe::Result {
         })
         .expect("Error al crear el hilo para comunicación");
 
+    let (serial_tx, serial_rx): (Sender<SerialRequest>, Receiver<SerialRequest>) = mpsc::channel();
+    let (event_tx, event_rx): (Sender<SerialEvent>, Receiver<SerialEvent>) = mpsc::channel();
+
+    let serial_thread = std::thread::Builder::new()
+        .name("serial_thread".to_owned())
+        .spawn(move || SerialThread::new(serial_rx, event_tx).run())
+        .expect("Error al crear el hilo para comunicación serial");
+
     let egui_logger = Box::new(egui_logger::builder().show_all_categories(false).build());
     let env_logger = Box::new(env_logger::builder().default_format().build());
 
@@ -45,9 +58,23 @@ fn main() -> eframe::Result {
             ),
         ..Default::default()
     };
-    eframe::run_native(
-        "SEPIC - Grupo 1 - Taller de Sistemas Electrónicos",
-        native_options,
-        Box::new(move |cc| Ok(Box::new(sepic_gui::SepicApp::new(cc, tx1, rx2)))),
-    )
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        eframe::run_native(
+            "SEPIC - Grupo 1 - Taller de Sistemas Electrónicos",
+            native_options,
+            Box::new(move |cc| {
+                Ok(Box::new(sepic_gui::SepicApp::new(
+                    cc, tx1, rx2, serial_tx, event_rx,
+                )))
+            }),
+        )
+    }));
+
+    // Al cerrarse la interfaz, incluso por un pánico, el hilo serial apaga el
+    // convertidor si nadie lo hizo antes; se le espera para no cortarlo a medias.
+    if serial_thread.join().is_err() {
+        error!("El hilo serial terminó con un pánico");
+    }
+
+    result.unwrap_or_else(|payload| panic::resume_unwind(payload))
 }
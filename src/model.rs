@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::serialcomms::{DutyQ9, FrequencyHz};
+
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+pub enum SetpointError {
+    #[error("El voltaje de entrada debe ser un número positivo")]
+    InvalidInput,
+    #[error("El voltaje de salida debe ser un número no negativo")]
+    InvalidOutput,
+    #[error(
+        "Se necesita un ciclo de trabajo de {required:.1} %, mayor al máximo de {max_duty} (salida máxima de {max_output:.2} V)"
+    )]
+    AboveMaximum {
+        required: f32,
+        max_duty: DutyQ9,
+        max_output: f32,
+    },
+}
+
+/// Voltaje de salida del SEPIC ideal en conducción continua,
+/// `Vo = Vin · D / (1 − D)`.
+pub fn ideal_output(vin: f32, duty_cycle: DutyQ9) -> f32 {
+    let duty = duty_cycle.percent() / 100.0;
+    vin * duty / (1.0 - duty)
+}
+
+/// Ciclo de trabajo con el que el SEPIC ideal entrega `vout`, inverso de
+/// [`ideal_output`]: `D = Vo / (Vin + Vo)`.
+pub fn ideal_duty(vin: f32, vout: f32, max_duty: DutyQ9) -> Result<DutyQ9, SetpointError> {
+    if !vin.is_finite() || vin <= 0.0 {
+        return Err(SetpointError::InvalidInput);
+    }
+    if !vout.is_finite() || vout < 0.0 {
+        return Err(SetpointError::InvalidOutput);
+    }
+
+    let required = vout / (vin + vout) * 100.0;
+    let duty_cycle = DutyQ9::from_percent(required).map_err(|_err| SetpointError::InvalidOutput)?;
+    if duty_cycle > max_duty {
+        return Err(SetpointError::AboveMaximum {
+            required,
+            max_duty,
+            max_output: ideal_output(vin, max_duty),
+        });
+    }
+
+    Ok(duty_cycle)
+}
+
+/// Modo de conducción de los inductores.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConductionMode {
+    Continuous,
+    Discontinuous,
+}
+
+impl ConductionMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Continuous => "CCM",
+            Self::Discontinuous => "DCM",
+        }
+    }
+}
+
+/// Punto de operación estimado por el modelo con pérdidas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OperatingPoint {
+    pub output: f32,
+    pub mode: ConductionMode,
+    /// `K = 2·Le·fs/R`; el convertidor opera en CCM mientras `K > Kcrit`.
+    pub k: f32,
+    /// `Kcrit = (1 − D)²`.
+    pub k_crit: f32,
+    /// Resistencia de carga a partir de la cual el convertidor entra en DCM,
+    /// en Ω.
+    pub critical_load: f32,
+}
+
+/// Componentes del convertidor, en unidades del SI.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConverterParams {
+    pub l1: f32,
+    pub l2: f32,
+    /// Capacitor de acoplamiento.
+    pub cc: f32,
+    /// Capacitor de salida.
+    pub co: f32,
+    pub rds_on: f32,
+    /// Caída directa del diodo.
+    pub vf: f32,
+    /// Resistencia serie de cada inductor.
+    pub dcr: f32,
+    /// Resistencia de carga.
+    pub load: f32,
+}
+
+impl Default for ConverterParams {
+    fn default() -> Self {
+        Self {
+            l1: 100e-6,
+            l2: 100e-6,
+            cc: 10e-6,
+            co: 100e-6,
+            rds_on: 0.05,
+            vf: 0.5,
+            dcr: 0.05,
+            load: 50.0,
+        }
+    }
+}
+
+impl ConverterParams {
+    /// Inductancia equivalente `Le = L1·L2 / (L1 + L2)`.
+    pub fn equivalent_inductance(&self) -> f32 {
+        self.l1 * self.l2 / (self.l1 + self.l2)
+    }
+
+    /// Estima el voltaje de salida con las pérdidas de conducción. En CCM
+    /// `Vo = (Vin·D/(1−D) − Vf) / (1 + Req/R)`, con
+    /// `Req = DCR·(D²/(1−D)² + 1) + Rds·D/(1−D)²`. En DCM se usa la ganancia
+    /// ideal `D/√K` descontando la caída del diodo.
+    pub fn operating_point(
+        &self,
+        vin: f32,
+        duty_cycle: DutyQ9,
+        frequency: FrequencyHz,
+    ) -> OperatingPoint {
+        let duty = duty_cycle.percent() / 100.0;
+        let off = 1.0 - duty;
+
+        let le_fs = 2.0 * self.equivalent_inductance() * frequency.hz();
+        let k = le_fs / self.load;
+        let k_crit = off * off;
+        let mode = if k > k_crit {
+            ConductionMode::Continuous
+        } else {
+            ConductionMode::Discontinuous
+        };
+
+        let output = match mode {
+            ConductionMode::Continuous => {
+                let req = self.dcr * (duty * duty / k_crit + 1.0) + self.rds_on * duty / k_crit;
+                (vin * duty / off - self.vf) / (1.0 + req / self.load)
+            }
+            ConductionMode::Discontinuous => vin * duty / k.sqrt() - self.vf,
+        };
+
+        OperatingPoint {
+            output: output.max(0.0),
+            mode,
+            k,
+            k_crit,
+            critical_load: le_fs / k_crit,
+        }
+    }
+}
+
+/// Rizados y esfuerzos de los componentes, válidos en CCM.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stresses {
+    pub output: f32,
+    pub output_current: f32,
+    pub input_current: f32,
+    /// Rizado pico a pico de la corriente en L1 y L2, en A.
+    pub l1_ripple: f32,
+    pub l2_ripple: f32,
+    /// Rizado pico a pico del voltaje de salida y del capacitor de
+    /// acoplamiento, en V.
+    pub output_ripple: f32,
+    pub coupling_ripple: f32,
+    /// Corriente pico del transistor y del diodo, `I1 + Io + (ΔI1 + ΔI2)/2`.
+    pub peak_current: f32,
+    /// Voltaje que bloquean el transistor y el diodo, `Vin + Vo`.
+    pub blocking_voltage: f32,
+    /// Corriente RMS del capacitor de acoplamiento, `Io·√(D/(1−D))`.
+    pub coupling_rms_current: f32,
+}
+
+impl ConverterParams {
+    pub fn stresses(&self, vin: f32, duty_cycle: DutyQ9, frequency: FrequencyHz) -> Stresses {
+        let output = self.operating_point(vin, duty_cycle, frequency).output;
+        let duty = duty_cycle.percent() / 100.0;
+        let fs = frequency.hz();
+
+        let output_current = output / self.load;
+        let input_current = output_current * duty / (1.0 - duty);
+        let l1_ripple = vin * duty / (fs * self.l1);
+        let l2_ripple = vin * duty / (fs * self.l2);
+
+        Stresses {
+            output,
+            output_current,
+            input_current,
+            l1_ripple,
+            l2_ripple,
+            output_ripple: output_current * duty / (fs * self.co),
+            coupling_ripple: output_current * duty / (fs * self.cc),
+            peak_current: input_current + output_current + f32::midpoint(l1_ripple, l2_ripple),
+            blocking_voltage: vin + output,
+            coupling_rms_current: output_current * (duty / (1.0 - duty)).sqrt(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConductionMode, ConverterParams, SetpointError, ideal_duty, ideal_output};
+    use crate::serialcomms::{DutyQ9, FrequencyHz};
+
+    fn frequency() -> FrequencyHz {
+        FrequencyHz::from_raw(100_000)
+    }
+
+    /// Convertidor sin pérdidas resistivas, con `Le·fs = 5 Ω`.
+    fn lossless(vf: f32, load: f32) -> ConverterParams {
+        ConverterParams {
+            rds_on: 0.0,
+            dcr: 0.0,
+            vf,
+            load,
+            ..ConverterParams::default()
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32, context: &str) {
+        assert!(
+            (actual - expected).abs() <= 1e-3 * expected.abs().max(1.0),
+            "{context}: {actual} ≠ {expected}"
+        );
+    }
+
+    fn percent(percent: f32) -> DutyQ9 {
+        DutyQ9::from_percent(percent).expect("porcentaje válido")
+    }
+
+    #[test]
+    fn ideal_duty_inverts_ideal_output() {
+        for duty_cycle in [0.0, 10.0, 33.3, 50.0, 75.0, 90.0].map(percent) {
+            let vout = ideal_output(12.0, duty_cycle);
+            let inverse = ideal_duty(12.0, vout, DutyQ9::MAX).expect("salida alcanzable");
+            assert!(
+                inverse.raw().abs_diff(duty_cycle.raw()) <= 1,
+                "{duty_cycle} da {vout} V, que se invierte a {inverse}"
+            );
+        }
+        assert_eq!(
+            ideal_duty(12.0, 12.0, DutyQ9::MAX),
+            Ok(percent(50.0)),
+            "Vo = Vin con D = 50 %"
+        );
+    }
+
+    #[test]
+    fn ideal_duty_rejects_unreachable_outputs() {
+        let max_duty = percent(75.0);
+        let Err(SetpointError::AboveMaximum {
+            required,
+            max_duty: reported,
+            max_output,
+        }) = ideal_duty(12.0, 48.0, max_duty)
+        else {
+            panic!("48 V requiere un ciclo de 80 %");
+        };
+        assert!((required - 80.0).abs() < 1e-3, "ciclo requerido {required}");
+        assert_eq!(reported, max_duty, "máximo del dispositivo");
+        assert!(
+            (max_output - 36.0).abs() < 1e-3,
+            "salida máxima {max_output}"
+        );
+
+        assert_eq!(
+            ideal_duty(0.0, 5.0, max_duty),
+            Err(SetpointError::InvalidInput),
+            "sin entrada"
+        );
+        assert_eq!(
+            ideal_duty(12.0, -1.0, max_duty),
+            Err(SetpointError::InvalidOutput),
+            "salida negativa"
+        );
+    }
+
+    #[test]
+    fn lossless_ccm_matches_ideal_output() {
+        let params = lossless(0.0, 5.0);
+        for duty_cycle in [10.0, 40.0, 60.0].map(percent) {
+            let point = params.operating_point(12.0, duty_cycle, frequency());
+            assert_eq!(point.mode, ConductionMode::Continuous, "{duty_cycle}");
+            assert_close(
+                point.output,
+                ideal_output(12.0, duty_cycle),
+                &duty_cycle.to_string(),
+            );
+        }
+    }
+
+    #[test]
+    fn ccm_and_dcm_agree_at_the_boundary() {
+        let duty_cycle = percent(40.0);
+        let critical_load = lossless(0.5, 1.0)
+            .operating_point(12.0, duty_cycle, frequency())
+            .critical_load;
+        assert_close(critical_load, 10.0 / 0.36, "Rcrit = 2·Le·fs/(1 − D)²");
+
+        let ccm =
+            lossless(0.5, critical_load * 0.9999).operating_point(12.0, duty_cycle, frequency());
+        let dcm =
+            lossless(0.5, critical_load * 1.0001).operating_point(12.0, duty_cycle, frequency());
+        assert_eq!(ccm.mode, ConductionMode::Continuous, "bajo Rcrit");
+        assert_eq!(dcm.mode, ConductionMode::Discontinuous, "sobre Rcrit");
+        assert_close(dcm.output, ccm.output, "continuidad en la frontera");
+        assert_close(
+            ccm.output,
+            ideal_output(12.0, duty_cycle) - 0.5,
+            "caída del diodo",
+        );
+    }
+
+    #[test]
+    fn losses_reduce_the_output() {
+        let duty_cycle = percent(50.0);
+        let ideal = lossless(0.0, 5.0).operating_point(12.0, duty_cycle, frequency());
+        let lossy = ConverterParams {
+            load: 5.0,
+            ..ConverterParams::default()
+        }
+        .operating_point(12.0, duty_cycle, frequency());
+        assert!(lossy.output < ideal.output, "{lossy:?} vs {ideal:?}");
+    }
+
+    #[test]
+    fn lossless_stresses_balance_power() {
+        let params = lossless(0.0, 5.0);
+        let duty_cycle = percent(40.0);
+        let stresses = params.stresses(12.0, duty_cycle, frequency());
+
+        assert_close(
+            stresses.output,
+            ideal_output(12.0, duty_cycle),
+            "salida ideal",
+        );
+        assert_close(
+            12.0 * stresses.input_current,
+            stresses.output * stresses.output_current,
+            "potencia de entrada y salida",
+        );
+        assert_close(
+            stresses.blocking_voltage,
+            12.0 + stresses.output,
+            "Vin + Vo",
+        );
+        assert_close(
+            stresses.l1_ripple,
+            12.0 * 0.4 / (100e3 * params.l1),
+            "ΔI1 = Vin·D/(fs·L1)",
+        );
+        assert_close(
+            stresses.peak_current,
+            stresses.input_current + stresses.output_current + stresses.l1_ripple,
+            "con L1 = L2 el pico suma un rizado completo",
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::serialcomms::{DutyQ9, FrequencyHz};
+
+/// Forma de la trayectoria del ciclo de trabajo durante una rampa.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RampShape {
+    Linear,
+    /// Cambio suave al inicio y al final de la rampa.
+    SCurve,
+    /// Saltos iguales repartidos uniformemente en la duración de la rampa.
+    Stepped {
+        steps: u32,
+    },
+}
+
+impl RampShape {
+    /// Intervalo entre pasos cuando la rampa se ejecuta desde la interfaz.
+    const STEP_INTERVAL: Duration = Duration::from_millis(50);
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Linear => "Lineal",
+            Self::SCurve => "Curva S",
+            Self::Stepped { .. } => "Escalonada",
+        }
+    }
+
+    /// Fracción del cambio total alcanzada en la fracción `t` de la rampa.
+    fn progress(self, t: f64) -> f64 {
+        match self {
+            Self::Linear => t,
+            Self::SCurve => t * t * (3.0 - 2.0 * t),
+            Self::Stepped { steps } => {
+                let steps = f64::from(steps.max(1));
+                (t * steps).floor() / steps
+            }
+        }
+    }
+
+    /// Secuencia de valores a aplicar, con su instante relativo al inicio de
+    /// la rampa. El último paso siempre es `end`.
+    pub fn schedule(self, start: DutyQ9, end: DutyQ9, tspan: Duration) -> Vec<(Duration, DutyQ9)> {
+        // Nunca hay más pasos distintos que valores Q9 entre los extremos, lo
+        // que acota la memoria aunque `tspan` sea enorme.
+        let distinct = end.raw().abs_diff(start.raw());
+        let count = match self {
+            Self::Linear | Self::SCurve => {
+                (tspan.as_secs_f64() / Self::STEP_INTERVAL.as_secs_f64()).ceil() as u32
+            }
+            Self::Stepped { steps } => steps,
+        }
+        .min(distinct)
+        .max(1);
+
+        let start_raw = f64::from(start.raw());
+        let delta = f64::from(end.raw()) - start_raw;
+
+        let mut steps: Vec<(Duration, DutyQ9)> = Vec::with_capacity(count as usize);
+        for i in 1..=count {
+            let t = f64::from(i) / f64::from(count);
+            let duty = DutyQ9::from_raw((start_raw + delta * self.progress(t)).round() as u32);
+            if steps.last().is_some_and(|&(_, last)| last == duty) {
+                continue;
+            }
+            steps.push((tspan.mul_f64(t), duty));
+        }
+
+        steps
+    }
+}
+
+/// Forma en que se aplican los cambios del ciclo de trabajo.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RampProfile {
+    /// Cambio mínimo, en puntos porcentuales, a partir del cual se usa rampa.
+    pub threshold: f32,
+    /// Duración de la rampa, en ms.
+    pub duration_ms: u32,
+    /// Velocidad máxima de cambio, en %/s; cero la deja sin límite. Se aplica
+    /// también a los cambios por debajo del umbral.
+    pub slew_rate: f32,
+    pub shape: RampShape,
+}
+
+impl Default for RampProfile {
+    fn default() -> Self {
+        Self {
+            threshold: 15.0,
+            duration_ms: 1000,
+            slew_rate: 0.0,
+            shape: RampShape::Linear,
+        }
+    }
+}
+
+impl RampProfile {
+    /// Duración máxima de una rampa, que acota las velocidades de cambio
+    /// ínfimas.
+    pub const MAX_DURATION: Duration = Duration::from_secs(3600);
+
+    /// Duración con la que se debe pasar de `start` a `end`, o `None` si el
+    /// cambio se puede aplicar de una vez.
+    pub fn duration_for(&self, start: DutyQ9, end: DutyQ9) -> Option<Duration> {
+        let delta = (end.percent() - start.percent()).abs();
+
+        let ramp = if delta > self.threshold {
+            Duration::from_millis(u64::from(self.duration_ms))
+        } else {
+            Duration::ZERO
+        };
+        let slew = if self.slew_rate > 0.0 {
+            Duration::try_from_secs_f32(delta / self.slew_rate)
+                .map_or(Self::MAX_DURATION, |slew| slew.min(Self::MAX_DURATION))
+        } else {
+            Duration::ZERO
+        };
+
+        let tspan = ramp.max(slew);
+        (!tspan.is_zero()).then_some(tspan)
+    }
+}
+
+/// Forma en que se aplican los cambios de frecuencia.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrequencyRamp {
+    /// Duración de la rampa, en ms; cero aplica el cambio de una vez.
+    pub duration_ms: u32,
+    /// Tamaño de cada paso cuando la rampa se ejecuta desde la interfaz, en Hz.
+    pub step_hz: u32,
+}
+
+impl Default for FrequencyRamp {
+    fn default() -> Self {
+        Self {
+            duration_ms: 500,
+            step_hz: 1000,
+        }
+    }
+}
+
+impl FrequencyRamp {
+    pub fn duration(&self) -> Option<Duration> {
+        (self.duration_ms > 0).then(|| Duration::from_millis(u64::from(self.duration_ms)))
+    }
+
+    /// Secuencia de pasos de a lo sumo `step_hz` repartidos uniformemente en
+    /// la duración de la rampa. El último paso siempre es `end`.
+    pub fn schedule(&self, start: FrequencyHz, end: FrequencyHz) -> Vec<(Duration, FrequencyHz)> {
+        let tspan = self.duration().unwrap_or_default();
+        let delta = i64::from(end.raw()) - i64::from(start.raw());
+        let count = delta
+            .unsigned_abs()
+            .div_ceil(u64::from(self.step_hz.max(1)))
+            .max(1);
+        let count = u32::try_from(count).unwrap_or(u32::MAX);
+
+        (1..=count)
+            .map(|i| {
+                let raw = i64::from(start.raw()) + delta * i64::from(i) / i64::from(count);
+                let frequency = u32::try_from(raw).map_or(end, FrequencyHz::from_raw);
+                (tspan.mul_f64(f64::from(i) / f64::from(count)), frequency)
+            })
+            .collect()
+    }
+}
+
+/// Pasos pendientes de una rampa que se ejecuta desde la interfaz.
+pub struct HostRamp<T> {
+    steps: VecDeque<(Instant, T)>,
+}
+
+impl<T> Default for HostRamp<T> {
+    fn default() -> Self {
+        Self {
+            steps: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Copy> HostRamp<T> {
+    /// Reemplaza la rampa en curso por una que comienza ahora.
+    pub fn start(&mut self, schedule: Vec<(Duration, T)>) {
+        let now = Instant::now();
+        self.steps = schedule
+            .into_iter()
+            .map(|(offset, value)| (now + offset, value))
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.steps.front().map(|&(due, _)| due)
+    }
+
+    /// Quita los pasos vencidos y devuelve el último de ellos, de modo que si
+    /// el hilo se atrasó se salta directamente al valor que corresponde.
+    pub fn take_due(&mut self, now: Instant) -> Option<T> {
+        let mut value = None;
+        while let Some(&(due, step)) = self.steps.front() {
+            if due > now {
+                break;
+            }
+            value = Some(step);
+            self.steps.pop_front();
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{FrequencyRamp, HostRamp, RampProfile, RampShape};
+    use crate::serialcomms::{DutyQ9, FrequencyHz};
+
+    fn duty(raw: u32) -> DutyQ9 {
+        DutyQ9::from_raw(raw)
+    }
+
+    fn values<T: Copy>(schedule: &[(Duration, T)]) -> Vec<T> {
+        schedule.iter().map(|&(_, value)| value).collect()
+    }
+
+    #[test]
+    fn linear_schedule_ends_at_target() {
+        let tspan = Duration::from_millis(500);
+        let schedule = RampShape::Linear.schedule(duty(0), duty(1000), tspan);
+
+        assert_eq!(schedule.len(), 10, "un paso cada 50 ms");
+        assert_eq!(
+            schedule.last(),
+            Some(&(tspan, duty(1000))),
+            "termina en `end`"
+        );
+        assert!(
+            schedule.windows(2).all(|pair| pair.first() < pair.get(1)),
+            "avanza en el tiempo y en el valor"
+        );
+    }
+
+    #[test]
+    fn stepped_schedule_spreads_steps_evenly() {
+        let schedule =
+            RampShape::Stepped { steps: 4 }.schedule(duty(400), duty(0), Duration::from_secs(1));
+        assert_eq!(
+            schedule,
+            [
+                (Duration::from_millis(250), duty(300)),
+                (Duration::from_millis(500), duty(200)),
+                (Duration::from_millis(750), duty(100)),
+                (Duration::from_secs(1), duty(0)),
+            ],
+            "cuatro saltos iguales hacia abajo"
+        );
+    }
+
+    #[test]
+    fn s_curve_is_monotonic() {
+        let schedule = RampShape::SCurve.schedule(duty(0), duty(5000), Duration::from_secs(2));
+        let values = values(&schedule);
+        assert!(values.is_sorted(), "la curva S no retrocede");
+        assert_eq!(values.last(), Some(&duty(5000)), "termina en `end`");
+    }
+
+    #[test]
+    fn schedule_is_bounded_by_distinct_values() {
+        let tspan = Duration::from_secs(3600);
+        let schedule = RampShape::Linear.schedule(duty(10), duty(13), tspan);
+        assert_eq!(
+            values(&schedule),
+            [duty(11), duty(12), duty(13)],
+            "no hay más pasos que valores Q9 intermedios"
+        );
+
+        assert_eq!(
+            RampShape::Stepped { steps: u32::MAX }.schedule(duty(7), duty(7), tspan),
+            [(tspan, duty(7))],
+            "sin cambio queda un único paso"
+        );
+    }
+
+    #[test]
+    fn profile_duration() {
+        let profile = RampProfile {
+            threshold: 10.0,
+            duration_ms: 800,
+            slew_rate: 0.0,
+            shape: RampShape::Linear,
+        };
+        let percent = |percent| DutyQ9::from_percent(percent).expect("porcentaje válido");
+
+        assert_eq!(
+            profile.duration_for(percent(20.0), percent(25.0)),
+            None,
+            "bajo el umbral el cambio es inmediato"
+        );
+        assert_eq!(
+            profile.duration_for(percent(40.0), percent(20.0)),
+            Some(Duration::from_millis(800)),
+            "sobre el umbral se usa la rampa"
+        );
+
+        let limited = RampProfile {
+            slew_rate: 5.0,
+            ..profile
+        };
+        assert_eq!(
+            limited.duration_for(percent(20.0), percent(25.0)),
+            Some(Duration::from_secs(1)),
+            "5 puntos a 5 %/s"
+        );
+
+        let crawling = RampProfile {
+            slew_rate: f32::MIN_POSITIVE,
+            ..profile
+        };
+        assert_eq!(
+            crawling.duration_for(percent(0.0), percent(100.0)),
+            Some(RampProfile::MAX_DURATION),
+            "una velocidad ínfima no desborda"
+        );
+    }
+
+    #[test]
+    fn frequency_schedule_uses_bounded_steps() {
+        let ramp = FrequencyRamp {
+            duration_ms: 300,
+            step_hz: 1000,
+        };
+        let schedule = ramp.schedule(
+            FrequencyHz::from_raw(100_000),
+            FrequencyHz::from_raw(97_500),
+        );
+        assert_eq!(
+            values(&schedule),
+            [99_167, 98_334, 97_500].map(FrequencyHz::from_raw),
+            "tres pasos de a lo sumo 1 kHz"
+        );
+        assert_eq!(
+            schedule.last().map(|&(offset, _)| offset),
+            Some(Duration::from_millis(300)),
+            "el último paso cae al final de la rampa"
+        );
+    }
+
+    #[test]
+    fn host_ramp_skips_to_latest_due_step() {
+        let mut ramp = HostRamp::default();
+        ramp.start(vec![
+            (Duration::ZERO, 1),
+            (Duration::ZERO, 2),
+            (Duration::from_secs(3600), 3),
+        ]);
+
+        assert_eq!(
+            ramp.take_due(Instant::now()),
+            Some(2),
+            "salta al último vencido"
+        );
+        assert_eq!(ramp.take_due(Instant::now()), None, "el resto no vence");
+        assert!(!ramp.is_empty(), "queda el paso pendiente");
+    }
+}
@@ -0,0 +1,651 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use super::units::{DutyQ9, FrequencyHz};
+
+pub const STX: u8 = 0x02;
+pub const ETX: u8 = 0x03;
+pub const ENQ: u8 = 0x05;
+pub const ACK: u8 = 0x06;
+pub const NACK: u8 = 0x15;
+
+const MAX_FRAME_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    Crc8,
+    Crc16,
+}
+
+impl Checksum {
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Crc8 => 8,
+            Self::Crc16 => 16,
+        }
+    }
+
+    /// CRC-8 (polinomio 0x07) o CRC-16/CCITT-FALSE (polinomio 0x1021).
+    pub fn compute(self, data: &[u8]) -> u16 {
+        match self {
+            Self::Crc8 => u16::from(data.iter().fold(0u8, |crc, &byte| {
+                (0..8).fold(crc ^ byte, |crc, _| {
+                    if crc & 0x80 == 0 {
+                        crc << 1
+                    } else {
+                        (crc << 1) ^ 0x07
+                    }
+                })
+            })),
+            Self::Crc16 => data.iter().fold(0xFFFFu16, |crc, &byte| {
+                (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+                    if crc & 0x8000 == 0 {
+                        crc << 1
+                    } else {
+                        (crc << 1) ^ 0x1021
+                    }
+                })
+            }),
+        }
+    }
+
+    fn format(self, crc: u16) -> String {
+        match self {
+            Self::Crc8 => format!("{crc:02X}"),
+            Self::Crc16 => format!("{crc:04X}"),
+        }
+    }
+}
+
+/// Formato de las tramas en el enlace serial.
+///
+/// En el formato `Checked` el cuerpo de cada trama es `SS:datos*CRC`, con `SS`
+/// el número de secuencia en hexadecimal y el CRC calculado sobre `SS:datos`.
+/// El ACK o NACK del dispositivo va seguido del número de secuencia en crudo.
+///
+/// El dispositivo vuelve a `Legacy` con [`Command::ResetFraming`], que la
+/// interfaz envía al cerrar el enlace, o al recibir un `ENQ` sin verificación,
+/// de modo que un handshake siempre parte del formato `Legacy` aunque la
+/// sesión anterior no se haya cerrado limpiamente.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Framing {
+    #[default]
+    Legacy,
+    Checked(Checksum),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Enquiry,
+    Query,
+    Identify,
+    SetDuty(DutyQ9),
+    RampDuty {
+        start: DutyQ9,
+        end: DutyQ9,
+        tspan: u32,
+    },
+    SetFrequency(FrequencyHz),
+    RampFrequency {
+        start: FrequencyHz,
+        end: FrequencyHz,
+        tspan: u32,
+    },
+    Negotiate(Checksum),
+    /// Vuelve al formato `Legacy` tras el ACK.
+    ResetFraming,
+    Heartbeat,
+    /// Tiempo sin comandos, en ms, tras el cual el firmware baja el ciclo de
+    /// trabajo a cero. Un valor de cero desactiva el watchdog.
+    Watchdog(u32),
+    /// Corta la salida de inmediato, interrumpiendo cualquier rampa en curso.
+    Stop,
+    ReadInputVoltage,
+}
+
+impl Command {
+    pub fn payload(&self) -> Vec<u8> {
+        match self {
+            Self::Enquiry => vec![ENQ],
+            Self::Query => b"QRY".to_vec(),
+            Self::Identify => b"IDN".to_vec(),
+            Self::SetDuty(duty) => format!("DCS {:#x}", duty.raw()).into_bytes(),
+            Self::RampDuty { start, end, tspan } => {
+                format!("DCR {:#x} {:#x} {tspan:#x}", start.raw(), end.raw()).into_bytes()
+            }
+            Self::SetFrequency(freq) => format!("FQS {:#x}", freq.raw()).into_bytes(),
+            Self::RampFrequency { start, end, tspan } => {
+                format!("FQR {:#x} {:#x} {tspan:#x}", start.raw(), end.raw()).into_bytes()
+            }
+            Self::Negotiate(checksum) => format!("CRC {:#x}", checksum.bits()).into_bytes(),
+            Self::ResetFraming => b"CRC 0x0".to_vec(),
+            Self::Heartbeat => b"HBT".to_vec(),
+            Self::Watchdog(timeout) => format!("WDT {timeout:#x}").into_bytes(),
+            Self::Stop => b"STP".to_vec(),
+            Self::ReadInputVoltage => b"VIN".to_vec(),
+        }
+    }
+
+    pub fn encode(&self, framing: Framing, seq: u8) -> Vec<u8> {
+        let body = match framing {
+            Framing::Legacy => self.payload(),
+            Framing::Checked(checksum) => {
+                let mut body = format!("{seq:02X}:").into_bytes();
+                body.extend(self.payload());
+                let crc = checksum.compute(&body);
+                body.push(b'*');
+                body.extend(checksum.format(crc).into_bytes());
+                body
+            }
+        };
+
+        [&[STX][..], &body, &[ETX]].concat()
+    }
+
+    /// Cantidad de tramas de reporte que el dispositivo envía después del ACK.
+    pub fn expected_reports(&self) -> usize {
+        match self {
+            Self::Enquiry | Self::Query => 2,
+            Self::Identify => 4,
+            Self::ReadInputVoltage => 1,
+            Self::SetDuty(_)
+            | Self::RampDuty { .. }
+            | Self::SetFrequency(_)
+            | Self::RampFrequency { .. }
+            | Self::Negotiate(_)
+            | Self::ResetFraming
+            | Self::Heartbeat
+            | Self::Watchdog(_)
+            | Self::Stop => 0,
+        }
+    }
+
+    /// Indica si el dispositivo puede omitir los reportes. Las placas más
+    /// simples responden al `ENQ` solo con el ACK.
+    pub fn reports_optional(&self) -> bool {
+        matches!(self, Self::Enquiry)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Response {
+    Ack,
+    Nack,
+    Frequency(FrequencyHz),
+    Duty(DutyQ9),
+    /// Voltaje de entrada medido por el dispositivo, en mV.
+    InputVoltage(u32),
+    DeviceId(String),
+    Firmware(String),
+    ProtocolVersion {
+        major: u32,
+        minor: u32,
+    },
+    /// Límites de frecuencia y ciclo de trabajo, y banderas de
+    /// funcionalidades opcionales.
+    Capabilities {
+        min_frequency: FrequencyHz,
+        max_frequency: FrequencyHz,
+        max_duty: DutyQ9,
+        flags: u32,
+    },
+    /// Detalle opcional que el dispositivo envía después de un NACK.
+    Error {
+        code: NackCode,
+        reason: Option<String>,
+    },
+}
+
+impl Response {
+    pub fn is_report(&self) -> bool {
+        !matches!(self, Self::Ack | Self::Nack)
+    }
+
+    fn parse_report(body: &[u8]) -> Result<Self, CodecError> {
+        let text = str::from_utf8(body).map_err(|_err| CodecError::NotAscii)?;
+        let mut chunks = text.split_whitespace();
+
+        let tag = chunks.next().ok_or(CodecError::EmptyFrame)?;
+        let mut value = || -> Result<u32, CodecError> {
+            let chunk = chunks.next().ok_or_else(|| CodecError::MissingField {
+                frame: text.to_owned(),
+            })?;
+            parse_hex(chunk).ok_or_else(|| CodecError::InvalidValue {
+                frame: text.to_owned(),
+            })
+        };
+
+        match tag {
+            "FRQ" => Ok(Self::Frequency(FrequencyHz::from_raw(value()?))),
+            "DTY" => Ok(Self::Duty(DutyQ9::from_raw(value()?))),
+            "VIN" => Ok(Self::InputVoltage(value()?)),
+            "PRV" => Ok(Self::ProtocolVersion {
+                major: value()?,
+                minor: value()?,
+            }),
+            "CAP" => Ok(Self::Capabilities {
+                min_frequency: FrequencyHz::from_raw(value()?),
+                max_frequency: FrequencyHz::from_raw(value()?),
+                max_duty: DutyQ9::from_raw(value()?),
+                flags: value()?,
+            }),
+            "IDN" | "FWV" => {
+                let rest = chunks.collect::<Vec<_>>().join(" ");
+                if rest.is_empty() {
+                    return Err(CodecError::MissingField {
+                        frame: text.to_owned(),
+                    });
+                }
+                Ok(if tag == "IDN" {
+                    Self::DeviceId(rest)
+                } else {
+                    Self::Firmware(rest)
+                })
+            }
+            "ERR" => {
+                let code = NackCode::from(u8::try_from(value()?).map_err(|_err| {
+                    CodecError::InvalidValue {
+                        frame: text.to_owned(),
+                    }
+                })?);
+                let reason = chunks.collect::<Vec<_>>().join(" ");
+                Ok(Self::Error {
+                    code,
+                    reason: (!reason.is_empty()).then_some(reason),
+                })
+            }
+            _ => Err(CodecError::UnknownFrame(text.escape_default().to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NackCode {
+    OutOfRange,
+    Busy,
+    UnknownCommand,
+    ChecksumMismatch,
+    Other(u8),
+}
+
+impl From<u8> for NackCode {
+    fn from(code: u8) -> Self {
+        match code {
+            0x01 => Self::OutOfRange,
+            0x02 => Self::Busy,
+            0x03 => Self::UnknownCommand,
+            0x04 => Self::ChecksumMismatch,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl fmt::Display for NackCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange => f.write_str("valor fuera de rango"),
+            Self::Busy => f.write_str("dispositivo ocupado"),
+            Self::UnknownCommand => f.write_str("comando desconocido"),
+            Self::ChecksumMismatch => f.write_str("trama corrupta"),
+            Self::Other(code) => write!(f, "código de error {code:#04x}"),
+        }
+    }
+}
+
+/// Rechazo (NACK) de un comando por parte del dispositivo.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceError {
+    pub code: Option<NackCode>,
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("El dispositivo rechazó el comando")?;
+        match (&self.code, &self.reason) {
+            (Some(code), Some(reason)) => write!(f, ": {code} ({reason})"),
+            (Some(code), None) => write!(f, ": {code}"),
+            (None, Some(reason)) => write!(f, ": {reason}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+fn parse_hex(s: &str) -> Option<u32> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u32::from_str_radix(digits, 16).ok()
+}
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("Byte inesperado fuera de una trama: {0:#04x}")]
+    UnexpectedByte(u8),
+    #[error("La trama excede el largo máximo de {MAX_FRAME_LEN} bytes")]
+    FrameTooLong,
+    #[error("La trama contiene caracteres no ASCII")]
+    NotAscii,
+    #[error("La trama está vacía")]
+    EmptyFrame,
+    #[error("Trama no reconocida `{0}`")]
+    UnknownFrame(String),
+    #[error("Falta un campo en la trama `{frame}`")]
+    MissingField { frame: String },
+    #[error("Valor inválido en la trama `{frame}`")]
+    InvalidValue { frame: String },
+    #[error("La trama `{frame}` no tiene el formato con verificación")]
+    MalformedChecked { frame: String },
+    #[error("El CRC de la trama `{frame}` no coincide")]
+    ChecksumMismatch { frame: String },
+    #[error(
+        "Número de secuencia inesperado: se esperaba {expected:#04x}, se recibió {received:#04x}"
+    )]
+    SequenceMismatch { expected: u8, received: u8 },
+}
+
+/// Decodificador incremental de las respuestas del dispositivo.
+///
+/// Los bytes se acumulan con [`Decoder::push`] a medida que llegan desde el
+/// puerto, por lo que una respuesta puede estar repartida en varias lecturas.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    framing: Framing,
+    seq: u8,
+}
+
+impl Decoder {
+    /// Crea un decodificador para las respuestas al comando con número de
+    /// secuencia `seq`, que se ignora en el formato `Legacy`.
+    pub fn new(framing: Framing, seq: u8) -> Self {
+        Self {
+            buf: Vec::new(),
+            framing,
+            seq,
+        }
+    }
+
+    fn check_seq(&self, received: u8) -> Result<(), CodecError> {
+        if received == self.seq {
+            Ok(())
+        } else {
+            Err(CodecError::SequenceMismatch {
+                expected: self.seq,
+                received,
+            })
+        }
+    }
+
+    fn unwrap_checked<'a>(
+        &self,
+        body: &'a [u8],
+        checksum: Checksum,
+    ) -> Result<&'a [u8], CodecError> {
+        let text = str::from_utf8(body).map_err(|_err| CodecError::NotAscii)?;
+        let malformed = || CodecError::MalformedChecked {
+            frame: text.escape_default().to_string(),
+        };
+
+        let (data, crc) = text.rsplit_once('*').ok_or_else(malformed)?;
+        let crc = u16::from_str_radix(crc, 16).map_err(|_err| malformed())?;
+        if crc != checksum.compute(data.as_bytes()) {
+            return Err(CodecError::ChecksumMismatch {
+                frame: text.escape_default().to_string(),
+            });
+        }
+
+        let (seq, payload) = data.split_once(':').ok_or_else(malformed)?;
+        self.check_seq(u8::from_str_radix(seq, 16).map_err(|_err| malformed())?)?;
+
+        Ok(payload.as_bytes())
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Extrae la siguiente respuesta completa, o `None` si faltan bytes.
+    pub fn next_response(&mut self) -> Option<Result<Response, CodecError>> {
+        let start = self
+            .buf
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(self.buf.len());
+        self.buf.drain(..start);
+
+        let &first = self.buf.first()?;
+        match first {
+            ACK | NACK => {
+                let response = if first == ACK {
+                    Response::Ack
+                } else {
+                    Response::Nack
+                };
+
+                if self.framing == Framing::Legacy {
+                    self.buf.remove(0);
+                    return Some(Ok(response));
+                }
+
+                let &seq = self.buf.get(1)?;
+                self.buf.drain(..2);
+                Some(self.check_seq(seq).map(|()| response))
+            }
+            STX => {
+                let Some(end) = self.buf.iter().position(|&b| b == ETX) else {
+                    if self.buf.len() > MAX_FRAME_LEN {
+                        self.buf.clear();
+                        return Some(Err(CodecError::FrameTooLong));
+                    }
+                    return None;
+                };
+
+                let frame: Vec<u8> = self.buf.drain(..=end).collect();
+                let body = frame.get(1..end).unwrap_or_default();
+                Some(match self.framing {
+                    Framing::Legacy => Response::parse_report(body),
+                    Framing::Checked(checksum) => self
+                        .unwrap_checked(body, checksum)
+                        .and_then(Response::parse_report),
+                })
+            }
+            byte => {
+                self.buf.remove(0);
+                Some(Err(CodecError::UnexpectedByte(byte)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Checksum, CodecError, Command, Decoder, Framing, MAX_FRAME_LEN, NackCode, Response,
+    };
+    use crate::serialcomms::{DutyQ9, FrequencyHz};
+
+    fn next(decoder: &mut Decoder) -> Response {
+        decoder
+            .next_response()
+            .expect("falta una respuesta")
+            .expect("respuesta inválida")
+    }
+
+    #[test]
+    fn encodes_legacy_frames() {
+        let command = Command::RampDuty {
+            start: DutyQ9::from_raw(0x100),
+            end: DutyQ9::from_raw(0x3200),
+            tspan: 1000,
+        };
+        assert_eq!(
+            command.encode(Framing::Legacy, 7),
+            b"\x02DCR 0x100 0x3200 0x3e8\x03",
+            "la trama legacy no lleva número de secuencia"
+        );
+        assert_eq!(
+            Command::Enquiry.encode(Framing::Legacy, 0),
+            [0x02, 0x05, 0x03],
+            "el ENQ va envuelto en STX/ETX"
+        );
+    }
+
+    #[test]
+    fn decodes_responses_split_across_reads() {
+        let mut decoder = Decoder::new(Framing::Legacy, 0);
+        decoder.push(b"\0\x06\x02FRQ 0x1");
+        assert_eq!(next(&mut decoder), Response::Ack, "los ceros se descartan");
+        assert!(
+            decoder.next_response().is_none(),
+            "la trama todavía no está completa"
+        );
+
+        decoder.push(b"86a0\x03\x02DTY 0");
+        assert_eq!(
+            next(&mut decoder),
+            Response::Frequency(FrequencyHz::from_raw(100_000)),
+            "la trama se completa con la segunda lectura"
+        );
+        decoder.push(b"x3200\x03");
+        assert_eq!(
+            next(&mut decoder),
+            Response::Duty(DutyQ9::from_raw(0x3200)),
+            "una trama puede empezar al final de una lectura"
+        );
+        assert!(decoder.next_response().is_none(), "no quedan bytes");
+    }
+
+    #[test]
+    fn decodes_reports() {
+        let mut decoder = Decoder::new(Framing::Legacy, 0);
+        decoder.push(b"\x15\x02ERR 0x2 rampa en curso\x03\x02IDN SEPIC rev B\x03");
+        assert_eq!(next(&mut decoder), Response::Nack, "NACK");
+        assert_eq!(
+            next(&mut decoder),
+            Response::Error {
+                code: NackCode::Busy,
+                reason: Some("rampa en curso".to_owned()),
+            },
+            "detalle del NACK"
+        );
+        assert_eq!(
+            next(&mut decoder),
+            Response::DeviceId("SEPIC rev B".to_owned()),
+            "el identificador conserva los espacios"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let mut decoder = Decoder::new(Framing::Legacy, 0);
+        decoder.push(b"x\x02FRQ\x03\x02XYZ 0x1\x03");
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::UnexpectedByte(b'x')))
+            ),
+            "byte fuera de una trama"
+        );
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::MissingField { .. }))
+            ),
+            "falta el valor"
+        );
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::UnknownFrame(_)))
+            ),
+            "etiqueta desconocida"
+        );
+
+        decoder.push(&[0x02]);
+        decoder.push(&[b'A'; MAX_FRAME_LEN]);
+        assert!(
+            matches!(decoder.next_response(), Some(Err(CodecError::FrameTooLong))),
+            "trama sin ETX"
+        );
+        assert!(decoder.next_response().is_none(), "el búfer se descarta");
+    }
+
+    #[test]
+    fn crc_check_values() {
+        assert_eq!(Checksum::Crc8.compute(b"123456789"), 0xF4, "CRC-8");
+        assert_eq!(
+            Checksum::Crc16.compute(b"123456789"),
+            0x29B1,
+            "CRC-16/CCITT-FALSE"
+        );
+        assert_eq!(Checksum::Crc16.compute(b""), 0xFFFF, "valor inicial");
+    }
+
+    #[test]
+    fn encodes_checked_frames() {
+        let checksum = Checksum::Crc8;
+        let crc = checksum.compute(b"2A:DCS 0x200");
+        assert_eq!(
+            Command::SetDuty(DutyQ9::from_raw(0x200)).encode(Framing::Checked(checksum), 0x2A),
+            format!("\x022A:DCS 0x200*{crc:02X}\x03").into_bytes(),
+            "la trama lleva secuencia y CRC"
+        );
+    }
+
+    #[test]
+    fn decodes_checked_frames() {
+        let checksum = Checksum::Crc16;
+        let framing = Framing::Checked(checksum);
+        let frame =
+            |data: &str| format!("\x02{data}*{:04X}\x03", checksum.compute(data.as_bytes()));
+
+        let mut decoder = Decoder::new(framing, 0x2A);
+        decoder.push(b"\x06");
+        assert!(
+            decoder.next_response().is_none(),
+            "el ACK espera su número de secuencia"
+        );
+        decoder.push(b"\x2A");
+        assert_eq!(next(&mut decoder), Response::Ack, "ACK con secuencia");
+
+        decoder.push(frame("2A:FRQ 0x10").as_bytes());
+        assert_eq!(
+            next(&mut decoder),
+            Response::Frequency(FrequencyHz::from_raw(0x10)),
+            "reporte verificado"
+        );
+
+        decoder.push(frame("2B:FRQ 0x10").as_bytes());
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::SequenceMismatch {
+                    expected: 0x2A,
+                    received: 0x2B,
+                }))
+            ),
+            "secuencia de otro comando"
+        );
+
+        decoder.push(b"\x022A:FRQ 0x10*0000\x03\x02FRQ 0x10\x03");
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::ChecksumMismatch { .. }))
+            ),
+            "CRC incorrecto"
+        );
+        assert!(
+            matches!(
+                decoder.next_response(),
+                Some(Err(CodecError::MalformedChecked { .. }))
+            ),
+            "trama sin CRC"
+        );
+    }
+}
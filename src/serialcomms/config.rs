@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+/// Parámetros con los que se abre el puerto serial.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialConfig {
+    pub baudrate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+    pub timeout_ms: u64,
+    /// Nivel de DTR al abrir el puerto; `None` lo deja como esté, lo que
+    /// evita reiniciar las placas que se resetean con DTR.
+    pub dtr_on_open: Option<bool>,
+    /// Nivel de RTS al abrir el puerto; `None` lo deja como esté.
+    pub rts_on_open: Option<bool>,
+    /// Intervalo entre heartbeats, en ms; cero los desactiva.
+    pub heartbeat_ms: u64,
+    /// Tiempo sin comandos tras el cual el firmware lleva el ciclo de trabajo
+    /// a cero, en ms; cero desactiva el watchdog.
+    pub watchdog_ms: u32,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baudrate: 9600,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            timeout_ms: 500,
+            dtr_on_open: None,
+            rts_on_open: None,
+            heartbeat_ms: 1000,
+            watchdog_ms: 3000,
+        }
+    }
+}
+
+impl SerialConfig {
+    pub fn with_baudrate(baudrate: u32) -> Self {
+        Self {
+            baudrate,
+            ..Self::default()
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        (self.heartbeat_ms > 0).then(|| Duration::from_millis(self.heartbeat_ms))
+    }
+
+    /// Tiempo con el que se arma el watchdog, o `None` si debe quedar
+    /// desactivado. Solo se arma si el heartbeat está activo y cabe al menos
+    /// dos veces en el plazo, para que la interfaz viva nunca lo dispare.
+    pub fn watchdog_timeout(&self) -> Option<u32> {
+        let heartbeat = self.heartbeat_interval()?;
+        let watchdog = Duration::from_millis(u64::from(self.watchdog_ms));
+        (self.watchdog_ms > 0 && heartbeat * 2 <= watchdog).then_some(self.watchdog_ms)
+    }
+
+    pub fn open(&self, port_name: &str) -> serialport::Result<Box<dyn SerialPort>> {
+        let mut builder = serialport::new(port_name, self.baudrate)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(self.timeout());
+        builder = match self.dtr_on_open {
+            Some(level) => builder.dtr_on_open(level),
+            None => builder.preserve_dtr_on_open(),
+        };
+
+        let mut port = builder.open()?;
+        if let Some(level) = self.rts_on_open {
+            port.write_request_to_send(level)?;
+        }
+
+        Ok(port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SerialConfig;
+
+    fn config(heartbeat_ms: u64, watchdog_ms: u32) -> SerialConfig {
+        SerialConfig {
+            heartbeat_ms,
+            watchdog_ms,
+            ..SerialConfig::default()
+        }
+    }
+
+    #[test]
+    fn watchdog_requires_two_heartbeats() {
+        assert_eq!(config(1000, 3000).watchdog_timeout(), Some(3000), "holgado");
+        assert_eq!(
+            config(1500, 3000).watchdog_timeout(),
+            Some(3000),
+            "justo dos heartbeats"
+        );
+        assert_eq!(
+            config(1501, 3000).watchdog_timeout(),
+            None,
+            "menos de dos heartbeats"
+        );
+        assert_eq!(
+            config(3000, 3000).watchdog_timeout(),
+            None,
+            "heartbeat igual al plazo"
+        );
+        assert_eq!(
+            config(5000, 3000).watchdog_timeout(),
+            None,
+            "heartbeat mayor al plazo"
+        );
+    }
+
+    #[test]
+    fn watchdog_disabled() {
+        assert_eq!(config(1000, 0).watchdog_timeout(), None, "watchdog en cero");
+        assert_eq!(
+            config(0, 3000).watchdog_timeout(),
+            None,
+            "sin heartbeat la interfaz no puede mantenerlo"
+        );
+    }
+}
@@ -0,0 +1,731 @@
+use std::{
+    io::{self, Read, Write},
+    rc::Rc,
+};
+
+use anyhow::{Result, anyhow};
+use log::{debug, warn};
+use serialport::{ClearBuffer, SerialPort, SerialPortInfo, SerialPortType};
+
+mod codec;
+pub use codec::DeviceError;
+use codec::{Checksum, CodecError, Command, Decoder, Framing, NackCode, Response};
+
+mod units;
+pub use units::{DutyQ9, FrequencyHz};
+
+mod config;
+pub use config::SerialConfig;
+
+const MAX_ATTEMPTS: usize = 3;
+
+/// Velocidades con las que se puede comunicar el controlador.
+pub const BAUDRATES: [u32; 3] = [9600, 38400, 115200];
+
+/// Puerto serial abierto junto con el estado del protocolo negociado.
+pub struct SerialLink {
+    port: Box<dyn SerialPort>,
+    framing: Framing,
+    seq: u8,
+    capabilities: Capabilities,
+    /// Firmware anterior a la identificación, que solo conoce `ENQ` y los
+    /// comandos de ajuste.
+    legacy: bool,
+}
+
+impl SerialLink {
+    pub fn new(port: Box<dyn SerialPort>) -> Self {
+        Self {
+            port,
+            framing: Framing::Legacy,
+            seq: 0,
+            capabilities: Capabilities::default(),
+            legacy: false,
+        }
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    fn next_seq(&mut self) -> u8 {
+        self.seq = self.seq.wrapping_add(1);
+        self.seq
+    }
+}
+
+/// Representación imprimible de una trama. Los bytes fuera de ASCII, como el
+/// número de secuencia de las tramas verificadas, se muestran como `\xNN`.
+fn escape_frame(body: &[u8]) -> String {
+    let len = body
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+    body.get(..len)
+        .unwrap_or_default()
+        .escape_ascii()
+        .to_string()
+}
+
+pub fn get_serial_ports() -> Result<Vec<Rc<SerialPortInfo>>> {
+    Ok(serialport::available_ports()?
+        .into_iter()
+        .map(Rc::new)
+        .collect())
+}
+
+/// Número de serie USB del puerto, que se conserva aunque el sistema le
+/// asigne otro nombre al reconectarlo.
+pub fn usb_serial_number(port: &SerialPortInfo) -> Option<&str> {
+    match &port.port_type {
+        SerialPortType::UsbPort(usb) => usb.serial_number.as_deref(),
+        _ => None,
+    }
+}
+
+fn send_command(link: &mut SerialLink, cmd: &Command) -> Result<Vec<Response>> {
+    let attempts = match link.framing {
+        Framing::Legacy => 1,
+        Framing::Checked(_) => MAX_ATTEMPTS,
+    };
+    let seq = link.next_seq();
+
+    let mut attempt = 1;
+    loop {
+        match transact(&mut link.port, link.framing, seq, cmd) {
+            Err(e) if attempt < attempts && is_retryable(&e) => {
+                warn!("Retransmitiendo comando (intento {attempt}/{attempts}): {e}");
+                link.port.clear(ClearBuffer::Input)?;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_retryable(error: &anyhow::Error) -> bool {
+    if let Some(e) = error.downcast_ref::<io::Error>() {
+        e.kind() == io::ErrorKind::TimedOut
+    } else if let Some(e) = error.downcast_ref::<CodecError>() {
+        matches!(
+            e,
+            CodecError::ChecksumMismatch { .. } | CodecError::SequenceMismatch { .. }
+        )
+    } else if let Some(e) = error.downcast_ref::<DeviceError>() {
+        e.code == Some(NackCode::ChecksumMismatch)
+    } else {
+        false
+    }
+}
+
+fn transact<T: Write + Read + ?Sized>(
+    port: &mut Box<T>,
+    framing: Framing,
+    seq: u8,
+    cmd: &Command,
+) -> Result<Vec<Response>> {
+    let output_buf = cmd.encode(framing, seq);
+
+    debug!("Enviando mensaje `{}`", escape_frame(&output_buf));
+
+    port.write_all(&output_buf)?;
+
+    let mut decoder = Decoder::new(framing, seq);
+    let mut input_buf = [0u8; 64];
+    let mut acknowledged = false;
+    let mut rejected = false;
+    let mut reports = Vec::with_capacity(cmd.expected_reports());
+
+    while rejected || !acknowledged || reports.len() < cmd.expected_reports() {
+        if let Some(response) = decoder.next_response() {
+            match response? {
+                Response::Ack if !acknowledged && !rejected => acknowledged = true,
+                Response::Nack if !acknowledged && !rejected => rejected = true,
+                Response::Error { code, reason } if rejected => {
+                    return Err(DeviceError {
+                        code: Some(code),
+                        reason,
+                    }
+                    .into());
+                }
+                report if acknowledged && report.is_report() => reports.push(report),
+                _ => return Err(anyhow!("La respuesta del dispositivo no es la esperada")),
+            }
+            continue;
+        }
+
+        // Después de un NACK el dispositivo puede enviar opcionalmente una
+        // trama `ERR` con el detalle; si no llega nada el rechazo queda sin
+        // especificar. Del mismo modo, un ACK sin reportes opcionales
+        // completa la respuesta.
+        let omitted_reports = acknowledged && reports.is_empty() && cmd.reports_optional();
+        let bytes_read = match port.read(&mut input_buf) {
+            Ok(0) if rejected => return Err(DeviceError::default().into()),
+            Err(e) if rejected && e.kind() == io::ErrorKind::TimedOut => {
+                return Err(DeviceError::default().into());
+            }
+            Ok(0) if omitted_reports => return Ok(reports),
+            Err(e) if omitted_reports && e.kind() == io::ErrorKind::TimedOut => {
+                return Ok(reports);
+            }
+            Ok(n) => n,
+            Err(e) => return Err(e.into()),
+        };
+        let received = input_buf
+            .get(..bytes_read)
+            .ok_or(anyhow!("No se pudo seccionar la respuesta del dispositivo"))?;
+
+        debug!(
+            "Mensaje recibido de largo {} `{}`",
+            bytes_read,
+            escape_frame(received)
+        );
+
+        if bytes_read == 0 {
+            return Err(anyhow!("La respuesta no tiene el largo esperado"));
+        }
+        decoder.push(received);
+    }
+
+    Ok(reports)
+}
+
+/// Frecuencia y ciclo de trabajo reportados por el dispositivo.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeviceSettings {
+    pub frequency: FrequencyHz,
+    pub duty_cycle: DutyQ9,
+}
+
+impl DeviceSettings {
+    fn values(reports: &[Response]) -> (Option<FrequencyHz>, Option<DutyQ9>) {
+        let mut frequency = None;
+        let mut duty_cycle = None;
+
+        for report in reports {
+            match report {
+                Response::Frequency(freq) => frequency = Some(*freq),
+                Response::Duty(duty) => duty_cycle = Some(*duty),
+                _ => {}
+            }
+        }
+
+        (frequency, duty_cycle)
+    }
+
+    fn from_reports(reports: &[Response]) -> Result<Self> {
+        let (frequency, duty_cycle) = Self::values(reports);
+
+        Ok(Self {
+            frequency: frequency.ok_or(anyhow!("El dispositivo no reportó la frecuencia"))?,
+            duty_cycle: duty_cycle
+                .ok_or(anyhow!("El dispositivo no reportó el ciclo de trabajo"))?,
+        })
+    }
+
+    /// Valores reportados después del `ENQ`. Las placas que responden solo
+    /// con el ACK no informan nada, así que los valores que falten quedan en
+    /// cero.
+    fn from_enquiry(reports: &[Response]) -> Self {
+        let (frequency, duty_cycle) = Self::values(reports);
+        if frequency.is_none() || duty_cycle.is_none() {
+            debug!("El dispositivo no reportó su configuración tras el ENQ, se asume cero");
+        }
+
+        Self {
+            frequency: frequency.unwrap_or_default(),
+            duty_cycle: duty_cycle.unwrap_or_default(),
+        }
+    }
+
+    pub fn diverges_from(&self, frequency: FrequencyHz, duty_cycle: DutyQ9) -> bool {
+        self.frequency != frequency || self.duty_cycle != duty_cycle
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub min_frequency: FrequencyHz,
+    pub max_frequency: FrequencyHz,
+    pub max_duty: DutyQ9,
+    pub ramp: bool,
+    pub telemetry: bool,
+    pub watchdog: bool,
+    pub stop: bool,
+    pub frequency_ramp: bool,
+    pub input_voltage: bool,
+}
+
+impl Capabilities {
+    const RAMP: u32 = 1 << 0;
+    const TELEMETRY: u32 = 1 << 1;
+    const WATCHDOG: u32 = 1 << 2;
+    const STOP: u32 = 1 << 3;
+    const FREQUENCY_RAMP: u32 = 1 << 4;
+    const INPUT_VOLTAGE: u32 = 1 << 5;
+
+    /// Arma las capacidades reportadas por `CAP`, corrigiendo límites
+    /// inconsistentes para que no se usen rangos vacíos ni ciclos de trabajo
+    /// mayores al 100 %.
+    fn from_flags(
+        mut min_frequency: FrequencyHz,
+        mut max_frequency: FrequencyHz,
+        mut max_duty: DutyQ9,
+        flags: u32,
+    ) -> Self {
+        if min_frequency > max_frequency {
+            warn!(
+                "El dispositivo reportó una frecuencia mínima ({min_frequency}) mayor a la máxima ({max_frequency}), se intercambian"
+            );
+            (min_frequency, max_frequency) = (max_frequency, min_frequency);
+        }
+        if max_duty > DutyQ9::MAX {
+            warn!(
+                "El dispositivo reportó un ciclo de trabajo máximo de {max_duty}, se limita al 100 %"
+            );
+            max_duty = DutyQ9::MAX;
+        }
+
+        Self {
+            min_frequency,
+            max_frequency,
+            max_duty,
+            ramp: flags & Self::RAMP != 0,
+            telemetry: flags & Self::TELEMETRY != 0,
+            watchdog: flags & Self::WATCHDOG != 0,
+            stop: flags & Self::STOP != 0,
+            frequency_ramp: flags & Self::FREQUENCY_RAMP != 0,
+            input_voltage: flags & Self::INPUT_VOLTAGE != 0,
+        }
+    }
+
+    pub fn clamp_duty(&self, duty_cycle: DutyQ9) -> DutyQ9 {
+        duty_cycle.min(self.max_duty)
+    }
+
+    pub fn clamp_frequency(&self, frequency: FrequencyHz) -> FrequencyHz {
+        frequency.clamp(self.min_frequency, self.max_frequency)
+    }
+}
+
+impl Default for Capabilities {
+    /// Límites de las placas con firmware anterior a la identificación.
+    fn default() -> Self {
+        Self {
+            min_frequency: FrequencyHz::from_raw(60_000),
+            max_frequency: FrequencyHz::from_raw(120_000),
+            max_duty: DutyQ9::from_raw(75 << DutyQ9::FRACTION_BITS),
+            ramp: true,
+            telemetry: false,
+            watchdog: false,
+            stop: false,
+            frequency_ramp: false,
+            input_voltage: false,
+        }
+    }
+}
+
+/// Identificación del dispositivo obtenida durante el handshake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub id: String,
+    pub firmware: String,
+    pub protocol: (u32, u32),
+    pub capabilities: Capabilities,
+}
+
+impl DeviceInfo {
+    /// Versión mayor del protocolo que implementa esta interfaz.
+    pub const PROTOCOL_MAJOR: u32 = 1;
+
+    fn legacy() -> Self {
+        Self {
+            id: "desconocido".to_owned(),
+            firmware: "desconocido".to_owned(),
+            protocol: (0, 0),
+            capabilities: Capabilities::default(),
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.protocol.0 == 0
+    }
+
+    fn from_reports(reports: Vec<Response>) -> Result<Self> {
+        let mut info = Self::legacy();
+        let mut protocol = None;
+
+        for report in reports {
+            match report {
+                Response::DeviceId(id) => info.id = id,
+                Response::Firmware(firmware) => info.firmware = firmware,
+                Response::ProtocolVersion { major, minor } => protocol = Some((major, minor)),
+                Response::Capabilities {
+                    min_frequency,
+                    max_frequency,
+                    max_duty,
+                    flags,
+                } => {
+                    info.capabilities =
+                        Capabilities::from_flags(min_frequency, max_frequency, max_duty, flags);
+                }
+                _ => {}
+            }
+        }
+
+        info.protocol = protocol.ok_or(anyhow!(
+            "El dispositivo no reportó la versión del protocolo"
+        ))?;
+        Ok(info)
+    }
+}
+
+pub fn attempt_handshake(link: &mut SerialLink) -> Result<(DeviceInfo, DeviceSettings)> {
+    link.framing = Framing::Legacy;
+    let reports = send_command(link, &Command::Enquiry)?;
+    let settings = DeviceSettings::from_enquiry(&reports);
+
+    debug!(
+        "Dispositivo conectado con frecuencia {} y duty {}",
+        settings.frequency, settings.duty_cycle
+    );
+
+    let info = identify(link)?;
+    link.capabilities = info.capabilities;
+    link.legacy = info.is_legacy();
+    if !info.is_legacy() {
+        negotiate_framing(link)?;
+    }
+
+    Ok((info, settings))
+}
+
+/// Identifica el dispositivo sin negociar el formato de las tramas, de modo
+/// que el puerto queda como estaba para el handshake de la conexión real.
+pub fn probe_device(link: &mut SerialLink) -> Result<DeviceInfo> {
+    link.framing = Framing::Legacy;
+    send_command(link, &Command::Enquiry)?;
+    identify(link)
+}
+
+/// Devuelve el dispositivo al formato `Legacy` antes de cerrar el enlace.
+pub fn reset_framing(link: &mut SerialLink) -> Result<()> {
+    if link.framing == Framing::Legacy {
+        return Ok(());
+    }
+
+    send_command(link, &Command::ResetFraming)?;
+    link.framing = Framing::Legacy;
+    debug!("Tramas con verificación deshabilitadas");
+
+    Ok(())
+}
+
+/// Solicita la identificación del dispositivo. Los firmwares que no conocen
+/// el comando se tratan como dispositivos con protocolo 0.
+fn identify(link: &mut SerialLink) -> Result<DeviceInfo> {
+    let info = match send_command(link, &Command::Identify) {
+        Ok(reports) => DeviceInfo::from_reports(reports)?,
+        Err(e) => {
+            debug!("El dispositivo no admite identificación: {e}");
+            link.port.clear(ClearBuffer::Input)?;
+            return Ok(DeviceInfo::legacy());
+        }
+    };
+
+    let (major, minor) = info.protocol;
+    if major > DeviceInfo::PROTOCOL_MAJOR {
+        return Err(anyhow!(
+            "El dispositivo usa la versión {major}.{minor} del protocolo, incompatible con la versión {}.x de esta interfaz",
+            DeviceInfo::PROTOCOL_MAJOR
+        ));
+    }
+
+    debug!(
+        "Dispositivo `{}` con firmware `{}` y protocolo {major}.{minor}",
+        info.id, info.firmware
+    );
+
+    Ok(info)
+}
+
+/// Lee la frecuencia y el ciclo de trabajo del dispositivo. El firmware
+/// anterior a la identificación no conoce `QRY`, así que se le repite el
+/// `ENQ`; si responde solo con el ACK no hay valores que leer y se devuelve
+/// `None`.
+pub fn read_settings(link: &mut SerialLink) -> Result<Option<DeviceSettings>> {
+    let cmd = if link.legacy {
+        Command::Enquiry
+    } else {
+        Command::Query
+    };
+    let reports = send_command(link, &cmd)?;
+    if link.legacy && reports.is_empty() {
+        return Ok(None);
+    }
+    let settings = DeviceSettings::from_reports(&reports)?;
+
+    debug!(
+        "Valores leídos del dispositivo: {}, {}",
+        settings.frequency, settings.duty_cycle
+    );
+
+    Ok(Some(settings))
+}
+
+/// Lee el voltaje de entrada medido por el dispositivo, en V.
+pub fn read_input_voltage(link: &mut SerialLink) -> Result<f32> {
+    if !link.capabilities.input_voltage {
+        return Err(anyhow!("El dispositivo no mide el voltaje de entrada"));
+    }
+
+    let reports = send_command(link, &Command::ReadInputVoltage)?;
+    reports
+        .iter()
+        .find_map(|report| match report {
+            Response::InputVoltage(millivolts) => Some(*millivolts as f32 / 1000.0),
+            _ => None,
+        })
+        .ok_or(anyhow!("El dispositivo no reportó el voltaje de entrada"))
+}
+
+/// Intenta activar las tramas con CRC y número de secuencia. Si el firmware
+/// no reconoce el comando se mantiene el formato original.
+fn negotiate_framing(link: &mut SerialLink) -> Result<()> {
+    for checksum in [Checksum::Crc16, Checksum::Crc8] {
+        match send_command(link, &Command::Negotiate(checksum)) {
+            Ok(_) => {
+                debug!("Tramas con CRC-{} habilitadas", checksum.bits());
+                link.framing = Framing::Checked(checksum);
+                return Ok(());
+            }
+            Err(e) => {
+                if let Some(rejection) = e.downcast_ref::<DeviceError>()
+                    && rejection.code == Some(NackCode::OutOfRange)
+                {
+                    continue;
+                }
+                debug!("El dispositivo no admite tramas con verificación: {e}");
+                break;
+            }
+        }
+    }
+
+    link.port.clear(ClearBuffer::Input)?;
+    Ok(())
+}
+
+/// Indica al dispositivo que la interfaz sigue activa. Los firmwares sin
+/// watchdog no conocen `HBT`, así que con ellos basta una consulta.
+pub fn heartbeat(link: &mut SerialLink) -> Result<()> {
+    let cmd = if link.capabilities.watchdog {
+        Command::Heartbeat
+    } else {
+        Command::Query
+    };
+    send_command(link, &cmd)?;
+
+    Ok(())
+}
+
+/// Configura el watchdog del firmware. Devuelve `false` si el dispositivo no
+/// lo admite.
+pub fn arm_watchdog(link: &mut SerialLink, timeout_ms: u32) -> Result<bool> {
+    if !link.capabilities.watchdog {
+        return Ok(false);
+    }
+
+    send_command(link, &Command::Watchdog(timeout_ms))?;
+    debug!("Watchdog del dispositivo configurado en {timeout_ms} ms");
+
+    Ok(true)
+}
+
+/// Corta la salida del convertidor. Sin el comando `STP` se fija el ciclo de
+/// trabajo en cero, lo que no detiene una rampa que el firmware esté
+/// ejecutando.
+pub fn emergency_stop(link: &mut SerialLink) -> Result<()> {
+    let cmd = if link.capabilities.stop {
+        Command::Stop
+    } else {
+        Command::SetDuty(DutyQ9::ZERO)
+    };
+    send_command(link, &cmd)?;
+
+    Ok(())
+}
+
+pub fn set_duty(link: &mut SerialLink, duty_cycle: DutyQ9) -> Result<()> {
+    let duty_cycle = link.capabilities.clamp_duty(duty_cycle);
+    send_command(link, &Command::SetDuty(duty_cycle))?;
+
+    Ok(())
+}
+
+pub fn ramp_duty(
+    link: &mut SerialLink,
+    duty_start: DutyQ9,
+    duty_end: DutyQ9,
+    tspan: u32,
+) -> Result<()> {
+    send_command(
+        link,
+        &Command::RampDuty {
+            start: link.capabilities.clamp_duty(duty_start),
+            end: link.capabilities.clamp_duty(duty_end),
+            tspan,
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn set_frequency(link: &mut SerialLink, frequency: FrequencyHz) -> Result<()> {
+    let frequency = link.capabilities.clamp_frequency(frequency);
+    send_command(link, &Command::SetFrequency(frequency))?;
+    Ok(())
+}
+
+pub fn ramp_frequency(
+    link: &mut SerialLink,
+    frequency_start: FrequencyHz,
+    frequency_end: FrequencyHz,
+    tspan: u32,
+) -> Result<()> {
+    send_command(
+        link,
+        &Command::RampFrequency {
+            start: link.capabilities.clamp_frequency(frequency_start),
+            end: link.capabilities.clamp_frequency(frequency_end),
+            tspan,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        io::{self, Read, Write},
+    };
+
+    use super::{Capabilities, DeviceSettings, DutyQ9, FrequencyHz, transact};
+    use crate::serialcomms::codec::{Command, Framing};
+
+    /// Puerto que entrega las respuestas programadas y luego agota el tiempo
+    /// de espera, como un puerto serial sin datos pendientes.
+    struct MockPort {
+        input: VecDeque<u8>,
+    }
+
+    impl MockPort {
+        fn replying(bytes: &[u8]) -> Self {
+            Self {
+                input: bytes.iter().copied().collect(),
+            }
+        }
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.input.is_empty() {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn enquire(reply: &[u8]) -> DeviceSettings {
+        let reports = transact(
+            &mut Box::new(MockPort::replying(reply)),
+            Framing::Legacy,
+            0,
+            &Command::Enquiry,
+        )
+        .expect("el ENQ se acepta");
+        DeviceSettings::from_enquiry(&reports)
+    }
+
+    #[test]
+    fn enquiry_accepts_bare_ack() {
+        assert_eq!(enquire(b"\x06"), DeviceSettings::default(), "sin reportes");
+        assert_eq!(
+            enquire(b"\x06\x02FRQ 0x186a0\x03\x02DTY 0x3200\x03"),
+            DeviceSettings {
+                frequency: FrequencyHz::from_raw(100_000),
+                duty_cycle: DutyQ9::from_raw(0x3200),
+            },
+            "con reportes"
+        );
+    }
+
+    #[test]
+    fn query_requires_reports() {
+        let result = transact(
+            &mut Box::new(MockPort::replying(b"\x06")),
+            Framing::Legacy,
+            0,
+            &Command::Query,
+        );
+        assert!(result.is_err(), "el QRY sin reportes es un error");
+    }
+
+    #[test]
+    fn capabilities_repair_inconsistent_limits() {
+        let capabilities = Capabilities::from_flags(
+            FrequencyHz::from_raw(150_000),
+            FrequencyHz::from_raw(50_000),
+            DutyQ9::from_raw(120 << DutyQ9::FRACTION_BITS),
+            Capabilities::RAMP | Capabilities::STOP,
+        );
+
+        assert_eq!(
+            (capabilities.min_frequency, capabilities.max_frequency),
+            (
+                FrequencyHz::from_raw(50_000),
+                FrequencyHz::from_raw(150_000)
+            ),
+            "los límites invertidos se intercambian"
+        );
+        assert_eq!(capabilities.max_duty, DutyQ9::MAX, "se limita al 100 %");
+        assert!(
+            capabilities.ramp && capabilities.stop && !capabilities.watchdog,
+            "banderas"
+        );
+        assert_eq!(
+            capabilities.clamp_frequency(FrequencyHz::from_raw(10_000)),
+            FrequencyHz::from_raw(50_000),
+            "el rango corregido no está vacío"
+        );
+    }
+
+    #[test]
+    fn capabilities_keep_consistent_limits() {
+        let max_duty = DutyQ9::from_raw(80 << DutyQ9::FRACTION_BITS);
+        let capabilities = Capabilities::from_flags(
+            FrequencyHz::from_raw(60_000),
+            FrequencyHz::from_raw(60_000),
+            max_duty,
+            0,
+        );
+
+        assert_eq!(
+            capabilities.min_frequency, capabilities.max_frequency,
+            "un rango de un solo valor es válido"
+        );
+        assert_eq!(capabilities.max_duty, max_duty, "sin cambios");
+        assert_eq!(capabilities.clamp_duty(DutyQ9::MAX), max_duty, "límite");
+    }
+}
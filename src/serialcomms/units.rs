@@ -0,0 +1,182 @@
+use std::fmt;
+
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq)]
+pub enum UnitError {
+    #[error("El valor no es un número finito")]
+    NotFinite,
+    #[error("El ciclo de trabajo {0}% está fuera del rango 0–100 %")]
+    DutyOutOfRange(f32),
+    #[error("La frecuencia {0} Hz está fuera del rango representable")]
+    FrequencyOutOfRange(f32),
+}
+
+/// Ciclo de trabajo en el formato de punto fijo Q9 del dispositivo, donde el
+/// valor crudo corresponde a `porcentaje * 2^9`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DutyQ9(u32);
+
+impl DutyQ9 {
+    pub const FRACTION_BITS: u32 = 9;
+    /// Paso mínimo representable, en %.
+    pub const RESOLUTION: f32 = 1.0 / (1 << Self::FRACTION_BITS) as f32;
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(100 << Self::FRACTION_BITS);
+
+    /// Convierte un porcentaje al valor Q9 más cercano.
+    pub fn from_percent(percent: f32) -> Result<Self, UnitError> {
+        if !percent.is_finite() {
+            return Err(UnitError::NotFinite);
+        }
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(UnitError::DutyOutOfRange(percent));
+        }
+
+        let raw = (f64::from(percent) * f64::from(1u32 << Self::FRACTION_BITS)).round();
+        Ok(Self(raw as u32))
+    }
+
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+
+    pub fn percent(self) -> f32 {
+        (f64::from(self.0) / f64::from(1u32 << Self::FRACTION_BITS)) as f32
+    }
+}
+
+impl fmt::Display for DutyQ9 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.3} %", self.percent())
+    }
+}
+
+/// Frecuencia de conmutación en pasos enteros de 1 Hz.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrequencyHz(u32);
+
+impl FrequencyHz {
+    /// Paso mínimo representable, en Hz.
+    pub const RESOLUTION: f32 = 1.0;
+
+    /// Convierte una frecuencia en Hz al entero más cercano.
+    pub fn from_hz(hz: f32) -> Result<Self, UnitError> {
+        if !hz.is_finite() {
+            return Err(UnitError::NotFinite);
+        }
+
+        let raw = f64::from(hz).round();
+        if raw < 0.0 || raw > f64::from(u32::MAX) {
+            return Err(UnitError::FrequencyOutOfRange(hz));
+        }
+        Ok(Self(raw as u32))
+    }
+
+    pub fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+
+    pub fn hz(self) -> f32 {
+        self.0 as f32
+    }
+}
+
+impl fmt::Display for FrequencyHz {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.3} kHz", self.hz() / 1e3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DutyQ9, FrequencyHz, UnitError};
+
+    #[test]
+    fn duty_rounds_to_nearest_step() {
+        assert_eq!(
+            DutyQ9::from_percent(50.0),
+            Ok(DutyQ9::from_raw(50 << 9)),
+            "50 %"
+        );
+        assert_eq!(DutyQ9::from_percent(100.0), Ok(DutyQ9::MAX), "100 %");
+        assert_eq!(
+            DutyQ9::from_percent(DutyQ9::RESOLUTION * 0.6),
+            Ok(DutyQ9::from_raw(1)),
+            "se redondea hacia arriba"
+        );
+        assert_eq!(
+            DutyQ9::from_percent(DutyQ9::RESOLUTION * 0.4),
+            Ok(DutyQ9::ZERO),
+            "se redondea hacia abajo"
+        );
+        assert_eq!(
+            DutyQ9::from_percent(12.345).map(DutyQ9::raw),
+            Ok(6321),
+            "12.345 × 512 = 6320.64"
+        );
+    }
+
+    #[test]
+    fn duty_rejects_invalid_percentages() {
+        assert_eq!(
+            DutyQ9::from_percent(100.1),
+            Err(UnitError::DutyOutOfRange(100.1)),
+            "sobre 100 %"
+        );
+        assert_eq!(
+            DutyQ9::from_percent(-0.5),
+            Err(UnitError::DutyOutOfRange(-0.5)),
+            "negativo"
+        );
+        assert_eq!(
+            DutyQ9::from_percent(f32::NAN),
+            Err(UnitError::NotFinite),
+            "NaN"
+        );
+    }
+
+    #[test]
+    fn duty_round_trips_through_percent() {
+        for raw in [0, 1, 511, 512, 25_600, DutyQ9::MAX.raw()] {
+            let duty = DutyQ9::from_raw(raw);
+            assert_eq!(
+                DutyQ9::from_percent(duty.percent()),
+                Ok(duty),
+                "valor crudo {raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn frequency_rounds_to_nearest_hz() {
+        assert_eq!(
+            FrequencyHz::from_hz(99_999.6).map(FrequencyHz::raw),
+            Ok(100_000),
+            "se redondea al Hz más cercano"
+        );
+        assert_eq!(
+            FrequencyHz::from_hz(-0.4),
+            Ok(FrequencyHz::from_raw(0)),
+            "redondea a cero"
+        );
+        assert_eq!(
+            FrequencyHz::from_hz(-1.0),
+            Err(UnitError::FrequencyOutOfRange(-1.0)),
+            "negativa"
+        );
+        assert_eq!(
+            FrequencyHz::from_hz(f32::INFINITY),
+            Err(UnitError::NotFinite),
+            "infinita"
+        );
+    }
+}
@@ -12,6 +12,7 @@ impl LogConsole {
             .enable_max_log_output(false)
             .enable_category("sepic_gui::app", true)
             .enable_category("sepic_gui::serialcomms", true)
+            .enable_category("sepic_gui::threading::serial", true)
             .show(ui);
     }
 }
@@ -1,43 +1,244 @@
 use anyhow::anyhow;
 use chrono::{DateTime, Local, TimeDelta};
-use std::{cell::RefCell, collections::VecDeque, rc::Rc, str::FromStr};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, VecDeque},
+    rc::Rc,
+    str::FromStr,
+};
 
-use egui_plot::{Line, Plot, PlotPoints};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
 
+/// Magnitud reportada por el monitor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Channel {
+    Vout,
+    Vin,
+    Iin,
+    Iout,
+    Temperature,
+}
+
+impl Channel {
+    pub const ALL: [Self; 5] = [
+        Self::Vout,
+        Self::Vin,
+        Self::Iin,
+        Self::Iout,
+        Self::Temperature,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            Self::Vout => 0,
+            Self::Vin => 1,
+            Self::Iin => 2,
+            Self::Iout => 3,
+            Self::Temperature => 4,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Vout => "Vout",
+            Self::Vin => "Vin",
+            Self::Iin => "Iin",
+            Self::Iout => "Iout",
+            Self::Temperature => "Temperatura",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Vout | Self::Vin => "V",
+            Self::Iin | Self::Iout => "A",
+            Self::Temperature => "°C",
+        }
+    }
+
+    /// Canal correspondiente a un nombre de campo del datagrama, sin
+    /// distinguir mayúsculas.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key.to_ascii_lowercase().as_str() {
+            "vout" | "vo" => Some(Self::Vout),
+            "vin" | "vi" => Some(Self::Vin),
+            "iin" | "ii" => Some(Self::Iin),
+            "iout" | "io" => Some(Self::Iout),
+            "temp" | "temperature" | "t" => Some(Self::Temperature),
+            _ => None,
+        }
+    }
+}
+
+/// Valor de cada canal reportado, sin reservar memoria aparte: el búfer
+/// guarda cientos de miles de muestras.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Channels([Option<f64>; Channel::ALL.len()]);
+
+impl Channels {
+    pub fn get(&self, channel: Channel) -> Option<f64> {
+        self.0.get(channel.index()).copied().flatten()
+    }
+
+    pub fn insert(&mut self, channel: Channel, value: f64) {
+        if let Some(slot) = self.0.get_mut(channel.index()) {
+            *slot = Some(value);
+        }
+    }
+
+    /// Copia los canales presentes en `other`, conservando el resto.
+    pub fn update(&mut self, other: &Self) {
+        for (channel, value) in other.iter() {
+            self.insert(channel, value);
+        }
+    }
+
+    /// Canales presentes con su valor, en el orden de `Channel::ALL`.
+    pub fn iter(&self) -> impl Iterator<Item = (Channel, f64)> + '_ {
+        Channel::ALL
+            .into_iter()
+            .filter_map(|channel| Some((channel, self.get(channel)?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl FromIterator<(Channel, f64)> for Channels {
+    fn from_iter<I: IntoIterator<Item = (Channel, f64)>>(iter: I) -> Self {
+        let mut channels = Self::default();
+        for (channel, value) in iter {
+            channels.insert(channel, value);
+        }
+        channels
+    }
+}
+
+/// Muestra del monitor con el valor de cada canal reportado.
 pub struct Measurement {
     pub timestamp: DateTime<Local>,
-    pub value: f64,
+    pub channels: Channels,
 }
 
 impl Default for Measurement {
     fn default() -> Self {
         Self {
             timestamp: Local::now(),
-            value: 0.0,
+            channels: Channels::default(),
         }
     }
 }
 
+impl Measurement {
+    pub fn get(&self, channel: Channel) -> Option<f64> {
+        self.channels.get(channel)
+    }
+}
+
 impl FromStr for Measurement {
     type Err = anyhow::Error;
 
+    /// Acepta `<timestamp> <canal>=<valor> ...` y el formato anterior
+    /// `<timestamp> <valor>`, que se interpreta como `Vout`. Los canales
+    /// desconocidos se ignoran.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (tstamp, value) = s.split_once(' ').ok_or(anyhow!("ParseMeasurementError"))?;
+        let (tstamp, fields) = s
+            .trim()
+            .split_once(' ')
+            .ok_or(anyhow!("ParseMeasurementError"))?;
 
-        let tstamp_fromstr = tstamp
+        let timestamp = tstamp
             .parse::<DateTime<Local>>()
             .map_err(|e| anyhow!("ParseMeasurementError: {e}"))?;
-        let value_fromstr = value
-            .parse::<f64>()
-            .map_err(|e| anyhow!("ParseMeasurementError: {e}"))?;
+        let parse_value = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|e| anyhow!("ParseMeasurementError: {e}"))
+        };
+
+        let mut channels = Channels::default();
+        if fields.contains('=') {
+            for field in fields.split_whitespace() {
+                let (key, value) = field
+                    .split_once('=')
+                    .ok_or(anyhow!("ParseMeasurementError: campo `{field}` sin valor"))?;
+                if let Some(channel) = Channel::from_key(key) {
+                    channels.insert(channel, parse_value(value)?);
+                }
+            }
+        } else {
+            channels.insert(Channel::Vout, parse_value(fields.trim())?);
+        }
 
         Ok(Self {
-            timestamp: tstamp_fromstr,
-            value: value_fromstr,
+            timestamp,
+            channels,
         })
     }
 }
 
+/// Muestras recientes del monitor, junto con el último valor recibido de cada
+/// canal para no recorrer todo el historial en cada cuadro.
+pub struct MeasBuffer {
+    samples: VecDeque<Measurement>,
+    window: TimeDelta,
+    latest: Channels,
+}
+
+impl MeasBuffer {
+    /// Búfer que conserva las muestras de los últimos `window`, sea cual sea
+    /// la frecuencia de muestreo del monitor.
+    pub fn with_window(window: TimeDelta) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window,
+            latest: Channels::default(),
+        }
+    }
+
+    /// Agrega las muestras, descartando las anteriores a la ventana contada
+    /// desde la más reciente.
+    pub fn extend(&mut self, batch: Vec<Measurement>) {
+        for measurement in &batch {
+            self.latest.update(&measurement.channels);
+        }
+        self.samples.extend(batch);
+
+        let Some(newest) = self.samples.back().map(|measurement| measurement.timestamp) else {
+            return;
+        };
+        let oldest = newest - self.window;
+        let excess = self
+            .samples
+            .partition_point(|measurement| measurement.timestamp < oldest);
+        self.samples.drain(..excess);
+    }
+
+    pub fn samples(&self) -> &VecDeque<Measurement> {
+        &self.samples
+    }
+
+    pub fn back(&self) -> Option<&Measurement> {
+        self.samples.back()
+    }
+
+    /// Último valor recibido del canal, aunque la muestra ya se haya
+    /// descartado.
+    pub fn latest(&self, channel: Channel) -> Option<f64> {
+        self.latest.get(channel)
+    }
+
+    /// Canales que el monitor reportó alguna vez.
+    pub fn channels(&self) -> impl Iterator<Item = Channel> + '_ {
+        self.latest.iter().map(|(channel, _)| channel)
+    }
+}
+
 pub struct MeasPlot;
 
 impl MeasPlot {
@@ -49,31 +250,62 @@ impl MeasPlot {
     #[expect(unused)]
     const MICROSECS_PER_MILLISEC: f64 = 1e3;
 
+    /// Puntos por canal a partir de los cuales se diezma la gráfica.
+    const MAX_POINTS: usize = 5000;
+
     pub fn title() -> egui::WidgetText {
         "Monitor de salida".into()
     }
 
-    pub fn ui(ui: &mut egui::Ui, data: &Rc<RefCell<VecDeque<Measurement>>>, tspan: TimeDelta) {
+    pub fn ui(
+        ui: &mut egui::Ui,
+        data: &Rc<RefCell<MeasBuffer>>,
+        tspan: TimeDelta,
+        hidden: &mut BTreeSet<Channel>,
+    ) {
         let fallback_measurement = Measurement::default();
-        let data = data.borrow();
+        let buffer = data.borrow();
+        let data = buffer.samples();
 
         let last_measurement = data.back().unwrap_or(&fallback_measurement);
         let first_tstamp = last_measurement.timestamp - tspan;
+        // Las muestras llegan ordenadas, así que la ventana es un sufijo.
+        let start = data.partition_point(|measurement| measurement.timestamp <= first_tstamp);
+        let stride = (data.len() - start).div_ceil(Self::MAX_POINTS).max(1);
+        let window = || data.range(start..);
 
-        let points: PlotPoints<'_> = data
-            .iter()
-            .filter(|&measurement| measurement.timestamp > first_tstamp)
-            .map(|measurement| {
-                [
-                    measurement.timestamp.timestamp_micros() as f64,
-                    measurement.value,
-                ]
+        let present: BTreeSet<Channel> = buffer.channels().collect();
+
+        ui.horizontal(|ui| {
+            for &channel in &present {
+                let mut visible = !hidden.contains(&channel);
+                if ui.checkbox(&mut visible, channel.label()).changed() {
+                    if visible {
+                        hidden.remove(&channel);
+                    } else {
+                        hidden.insert(channel);
+                    }
+                }
+            }
+        });
+
+        let lines: Vec<Line<'_>> = Channel::ALL
+            .into_iter()
+            .filter(|channel| present.contains(channel) && !hidden.contains(channel))
+            .map(|channel| {
+                let points: PlotPoints<'_> = window()
+                    .step_by(stride)
+                    .filter_map(|measurement| {
+                        let value = measurement.get(channel)?;
+                        Some([measurement.timestamp.timestamp_micros() as f64, value])
+                    })
+                    .collect();
+                Line::new(format!("{} / {}", channel.label(), channel.unit()), points)
             })
             .collect();
 
-        let line = Line::new("vo", points);
-
         Plot::new("meas_plot")
+            .legend(Legend::default())
             .allow_scroll(true)
             .allow_zoom(true)
             .allow_boxed_zoom(true)
@@ -81,7 +313,68 @@ impl MeasPlot {
             .include_y(50.0)
             .include_y(0.0)
             .x_axis_label("Tiempo / s")
-            .y_axis_label("Voltaje / V")
-            .show(ui, |plot_ui| plot_ui.line(line));
+            .y_axis_label("Valor")
+            .show(ui, |plot_ui| {
+                for line in lines {
+                    plot_ui.line(line);
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Local, TimeDelta};
+
+    use super::{Channel, Channels, MeasBuffer, Measurement};
+
+    fn sample(start: DateTime<Local>, s: i64, channel: Channel) -> Measurement {
+        let mut channels = Channels::default();
+        channels.insert(channel, s as f64);
+        Measurement {
+            timestamp: start + TimeDelta::seconds(s),
+            channels,
+        }
+    }
+
+    #[test]
+    fn channels_keep_only_reported_values() {
+        let mut channels = Channels::default();
+        assert!(channels.is_empty(), "sin canales");
+        channels.insert(Channel::Iout, 2.0);
+        channels.insert(Channel::Vout, 12.0);
+        assert_eq!(channels.len(), 2, "dos canales");
+        assert_eq!(channels.get(Channel::Vin), None, "canal no reportado");
+        assert_eq!(
+            channels.iter().collect::<Vec<_>>(),
+            [(Channel::Vout, 12.0), (Channel::Iout, 2.0)],
+            "en el orden de Channel::ALL"
+        );
+    }
+
+    #[test]
+    fn buffer_keeps_the_time_window() {
+        let start = Local::now();
+        let mut buffer = MeasBuffer::with_window(TimeDelta::seconds(10));
+        buffer.extend(vec![sample(start, 0, Channel::Vin)]);
+        buffer.extend((1..=15).map(|s| sample(start, s, Channel::Vout)).collect());
+
+        let samples = buffer.samples();
+        assert_eq!(samples.len(), 11, "de 5 s a 15 s");
+        assert_eq!(
+            samples.front().map(|sample| sample.timestamp),
+            Some(start + TimeDelta::seconds(5)),
+            "la más antigua dentro de la ventana"
+        );
+        assert_eq!(
+            buffer.latest(Channel::Vin),
+            Some(0.0),
+            "el último valor sobrevive al descarte"
+        );
+        assert_eq!(
+            buffer.channels().collect::<Vec<_>>(),
+            [Channel::Vout, Channel::Vin],
+            "canales reportados"
+        );
     }
 }
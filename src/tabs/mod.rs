@@ -1,4 +1,8 @@
-use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    rc::Rc,
+};
 
 mod pwm_plot;
 use chrono::TimeDelta;
@@ -6,11 +10,19 @@ use pwm_plot::PWMPlot;
 
 mod meas_plot;
 use meas_plot::MeasPlot;
-pub use meas_plot::Measurement;
+pub use meas_plot::{Channel, Channels, MeasBuffer, Measurement};
 
 mod logger;
 use logger::LogConsole;
 
+mod ripple;
+use ripple::RipplePanel;
+
+use crate::{
+    model::ConverterParams,
+    serialcomms::{DutyQ9, FrequencyHz},
+};
+
 pub struct MyTabViewer {}
 
 impl MyTabViewer {
@@ -27,6 +39,7 @@ impl egui_dock::TabViewer for MyTabViewer {
         match tab {
             MyTab::PWMPlot { .. } => PWMPlot::title(),
             MyTab::MeasPlot { .. } => MeasPlot::title(),
+            MyTab::Ripple { .. } => RipplePanel::title(),
             MyTab::LogConsole => LogConsole::title(),
         }
     }
@@ -37,15 +50,31 @@ impl egui_dock::TabViewer for MyTabViewer {
                 frequency,
                 duty_cycle,
                 tspan,
-            } => PWMPlot::ui(ui, **frequency, **duty_cycle, *tspan),
-            MyTab::MeasPlot { data, tspan } => MeasPlot::ui(ui, data, *tspan),
+            } => PWMPlot::ui(ui, frequency.get().hz(), duty_cycle.get().percent(), *tspan),
+            MyTab::MeasPlot {
+                data,
+                tspan,
+                hidden,
+            } => MeasPlot::ui(ui, data, *tspan, hidden),
+            MyTab::Ripple {
+                frequency,
+                duty_cycle,
+                params,
+                vin,
+            } => RipplePanel::ui(
+                ui,
+                &params.get(),
+                vin.get(),
+                frequency.get(),
+                duty_cycle.get(),
+            ),
             MyTab::LogConsole => LogConsole::ui(ui),
         }
     }
 
     fn closeable(&mut self, tab: &mut Self::Tab) -> bool {
         match tab {
-            MyTab::PWMPlot { .. } | MyTab::MeasPlot { .. } => false,
+            MyTab::PWMPlot { .. } | MyTab::MeasPlot { .. } | MyTab::Ripple { .. } => false,
             MyTab::LogConsole => true,
         }
     }
@@ -53,19 +82,31 @@ impl egui_dock::TabViewer for MyTabViewer {
 
 pub enum MyTab {
     PWMPlot {
-        frequency: Rc<f32>,
-        duty_cycle: Rc<f32>,
+        frequency: Rc<Cell<FrequencyHz>>,
+        duty_cycle: Rc<Cell<DutyQ9>>,
         tspan: f64,
     },
     MeasPlot {
-        data: Rc<RefCell<VecDeque<Measurement>>>,
+        data: Rc<RefCell<MeasBuffer>>,
         tspan: TimeDelta,
+        /// Canales ocultos en la gráfica.
+        hidden: BTreeSet<Channel>,
+    },
+    Ripple {
+        frequency: Rc<Cell<FrequencyHz>>,
+        duty_cycle: Rc<Cell<DutyQ9>>,
+        params: Rc<Cell<ConverterParams>>,
+        vin: Rc<Cell<f32>>,
     },
     LogConsole,
 }
 
 impl MyTab {
-    pub fn pwm_window(frequency: Rc<f32>, duty_cycle: Rc<f32>, tspan: f64) -> Self {
+    pub fn pwm_window(
+        frequency: Rc<Cell<FrequencyHz>>,
+        duty_cycle: Rc<Cell<DutyQ9>>,
+        tspan: f64,
+    ) -> Self {
         Self::PWMPlot {
             frequency,
             duty_cycle,
@@ -73,8 +114,26 @@ impl MyTab {
         }
     }
 
-    pub fn meas_window(data: Rc<RefCell<VecDeque<Measurement>>>, tspan: TimeDelta) -> Self {
-        Self::MeasPlot { data, tspan }
+    pub fn meas_window(data: Rc<RefCell<MeasBuffer>>, tspan: TimeDelta) -> Self {
+        Self::MeasPlot {
+            data,
+            tspan,
+            hidden: BTreeSet::new(),
+        }
+    }
+
+    pub fn ripple_window(
+        frequency: Rc<Cell<FrequencyHz>>,
+        duty_cycle: Rc<Cell<DutyQ9>>,
+        params: Rc<Cell<ConverterParams>>,
+        vin: Rc<Cell<f32>>,
+    ) -> Self {
+        Self::Ripple {
+            frequency,
+            duty_cycle,
+            params,
+            vin,
+        }
     }
 
     pub fn log_window() -> Self {
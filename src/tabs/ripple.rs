@@ -0,0 +1,83 @@
+use egui::Grid;
+
+use crate::{
+    model::{ConductionMode, ConverterParams},
+    serialcomms::{DutyQ9, FrequencyHz},
+};
+
+pub struct RipplePanel;
+
+impl RipplePanel {
+    pub fn title() -> egui::WidgetText {
+        "Rizado y esfuerzos".into()
+    }
+
+    pub fn ui(
+        ui: &mut egui::Ui,
+        params: &ConverterParams,
+        vin: f32,
+        frequency: FrequencyHz,
+        duty_cycle: DutyQ9,
+    ) {
+        let point = params.operating_point(vin, duty_cycle, frequency);
+        let stresses = params.stresses(vin, duty_cycle, frequency);
+
+        ui.label(format!("Vin = {vin:.2} V · {duty_cycle} · {frequency}"));
+        if point.mode == ConductionMode::Discontinuous {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ El convertidor opera en DCM; los valores suponen CCM",
+            );
+        }
+        ui.separator();
+
+        Grid::new("ripple_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (label, value) in [
+                    ("Voltaje de salida", format!("{:.2} V", stresses.output)),
+                    (
+                        "Corriente de salida",
+                        format!("{:.3} A", stresses.output_current),
+                    ),
+                    (
+                        "Corriente de entrada",
+                        format!("{:.3} A", stresses.input_current),
+                    ),
+                    (
+                        "Rizado de corriente en L1",
+                        format!("{:.3} A", stresses.l1_ripple),
+                    ),
+                    (
+                        "Rizado de corriente en L2",
+                        format!("{:.3} A", stresses.l2_ripple),
+                    ),
+                    (
+                        "Rizado de voltaje de salida",
+                        format!("{:.1} mV", stresses.output_ripple * 1e3),
+                    ),
+                    (
+                        "Rizado de voltaje en Cc",
+                        format!("{:.1} mV", stresses.coupling_ripple * 1e3),
+                    ),
+                    (
+                        "Corriente pico en transistor y diodo",
+                        format!("{:.3} A", stresses.peak_current),
+                    ),
+                    (
+                        "Voltaje en transistor y diodo",
+                        format!("{:.2} V", stresses.blocking_voltage),
+                    ),
+                    (
+                        "Corriente RMS en Cc",
+                        format!("{:.3} A", stresses.coupling_rms_current),
+                    ),
+                ] {
+                    ui.label(label);
+                    ui.monospace(value);
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -0,0 +1,461 @@
+use std::{collections::BTreeMap, str::FromStr as _};
+
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::tabs::{Channel, Channels, Measurement};
+
+/// Primer byte de un datagrama binario, versión 1:
+/// `[0x01][timestamp: i64 LE, µs desde la época][n: u8][n × (canal: u8, valor: f32 LE)]`.
+const BINARY_V1: u8 = 0x01;
+/// Bloque de muestras, versión 2:
+/// `[0x02][base: i64 LE, µs][c: u8][c × canal: u8][n: u16 LE]` seguido de `n`
+/// muestras `[delta: u32 LE, µs desde base][c × valor: f32 LE]`.
+const BINARY_V2: u8 = 0x02;
+
+#[derive(Debug, Error)]
+pub enum TelemetryError {
+    #[error("Datagrama vacío")]
+    Empty,
+    #[error("Versión de formato binario desconocida: 0x{0:02X}")]
+    UnsupportedVersion(u8),
+    #[error("Se esperaban {expected} bytes y se recibieron {received}")]
+    Length { expected: usize, received: usize },
+    #[error("Marca de tiempo fuera de rango: {0} µs")]
+    Timestamp(i64),
+    #[error("El datagrama no es texto válido: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("Línea de texto inválida: {0}")]
+    Text(String),
+    #[error("Objeto JSON inválido: {0}")]
+    Json(String),
+}
+
+/// Decodifica un datagrama del monitor, que puede traer una o varias muestras.
+///
+/// Los formatos de texto (`<timestamp> <canal>=<valor> ...`, o el anterior
+/// `<timestamp> <valor>`) y JSON (un objeto por línea) se distinguen por
+/// línea; el binario, por su byte de versión.
+pub fn decode(datagram: &[u8]) -> Result<Vec<Measurement>, TelemetryError> {
+    let &first = datagram.first().ok_or(TelemetryError::Empty)?;
+    match first {
+        BINARY_V1 => decode_binary_v1(datagram).map(|measurement| vec![measurement]),
+        BINARY_V2 => decode_binary_v2(datagram),
+        version if version < b' ' && !version.is_ascii_whitespace() => {
+            Err(TelemetryError::UnsupportedVersion(version))
+        }
+        _ => decode_text(datagram),
+    }
+}
+
+fn decode_text(datagram: &[u8]) -> Result<Vec<Measurement>, TelemetryError> {
+    // Algunos emisores rellenan el datagrama con ceros hasta un largo fijo.
+    let text = str::from_utf8(datagram)?.trim_end_matches('\0');
+
+    let measurements: Vec<Measurement> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if line.starts_with('{') {
+                decode_json(line)
+            } else {
+                Measurement::from_str(line).map_err(|e| TelemetryError::Text(e.to_string()))
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    if measurements.is_empty() {
+        return Err(TelemetryError::Empty);
+    }
+    Ok(measurements)
+}
+
+/// Objeto JSON como `{"ts": "2025-01-01T12:00:00-03:00", "vout": 12.1}`. La
+/// marca de tiempo puede ser RFC 3339 o µs desde la época; los canales en
+/// `null` y los miembros desconocidos se ignoran.
+fn decode_json(line: &str) -> Result<Measurement, TelemetryError> {
+    let invalid = |reason: String| TelemetryError::Json(reason);
+    let members: BTreeMap<String, Value> =
+        serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+
+    let mut timestamp = None;
+    let mut channels = Channels::default();
+    for (key, value) in &members {
+        if matches!(key.as_str(), "ts" | "timestamp") {
+            timestamp = Some(match value {
+                Value::String(text) => text
+                    .parse::<DateTime<Local>>()
+                    .map_err(|e| invalid(e.to_string()))?,
+                Value::Number(micros) => {
+                    let micros = micros
+                        .as_i64()
+                        .or_else(|| micros.as_f64().map(|micros| micros.round() as i64))
+                        .ok_or_else(|| invalid(format!("marca de tiempo `{micros}`")))?;
+                    from_micros(micros)?
+                }
+                _ => return Err(invalid(format!("marca de tiempo `{value}`"))),
+            });
+        } else if let Some(channel) = Channel::from_key(key) {
+            match value {
+                Value::Null => {}
+                Value::Number(number) => {
+                    let number = number
+                        .as_f64()
+                        .ok_or_else(|| invalid(format!("valor `{number}` de `{key}`")))?;
+                    channels.insert(channel, number);
+                }
+                _ => return Err(invalid(format!("valor `{value}` de `{key}`"))),
+            }
+        }
+    }
+
+    Ok(Measurement {
+        timestamp: timestamp.ok_or_else(|| invalid("falta `ts`".to_owned()))?,
+        channels,
+    })
+}
+
+fn from_micros(micros: i64) -> Result<DateTime<Local>, TelemetryError> {
+    DateTime::from_timestamp_micros(micros)
+        .map(|timestamp| timestamp.with_timezone(&Local))
+        .ok_or(TelemetryError::Timestamp(micros))
+}
+
+/// Identificadores de canal del formato binario.
+fn channel_from_id(id: u8) -> Option<Channel> {
+    match id {
+        0 => Some(Channel::Vout),
+        1 => Some(Channel::Vin),
+        2 => Some(Channel::Iin),
+        3 => Some(Channel::Iout),
+        4 => Some(Channel::Temperature),
+        _ => None,
+    }
+}
+
+fn decode_binary_v1(datagram: &[u8]) -> Result<Measurement, TelemetryError> {
+    const HEADER_LEN: usize = 10;
+    const CHANNEL_LEN: usize = 5;
+
+    let truncated = |expected| TelemetryError::Length {
+        expected,
+        received: datagram.len(),
+    };
+    let rest = datagram.get(1..).unwrap_or_default();
+    let (&micros, rest) = rest
+        .split_first_chunk::<8>()
+        .ok_or_else(|| truncated(HEADER_LEN))?;
+    let (&count, mut rest) = rest.split_first().ok_or_else(|| truncated(HEADER_LEN))?;
+
+    let expected = HEADER_LEN + usize::from(count) * CHANNEL_LEN;
+    if datagram.len() != expected {
+        return Err(truncated(expected));
+    }
+
+    let mut channels = Channels::default();
+    while let Some((&id, tail)) = rest.split_first()
+        && let Some((&value, tail)) = tail.split_first_chunk::<4>()
+    {
+        // Los canales que esta versión no conoce se descartan.
+        if let Some(channel) = channel_from_id(id) {
+            channels.insert(channel, f64::from(f32::from_le_bytes(value)));
+        }
+        rest = tail;
+    }
+
+    Ok(Measurement {
+        timestamp: from_micros(i64::from_le_bytes(micros))?,
+        channels,
+    })
+}
+
+fn decode_binary_v2(datagram: &[u8]) -> Result<Vec<Measurement>, TelemetryError> {
+    let truncated = |expected| TelemetryError::Length {
+        expected,
+        received: datagram.len(),
+    };
+    let rest = datagram.get(1..).unwrap_or_default();
+    let (&base, rest) = rest.split_first_chunk::<8>().ok_or_else(|| truncated(10))?;
+    let (&channel_count, rest) = rest.split_first().ok_or_else(|| truncated(10))?;
+    let channel_count = usize::from(channel_count);
+
+    let header_len = 12 + channel_count;
+    let (ids, rest) = rest
+        .split_at_checked(channel_count)
+        .ok_or_else(|| truncated(header_len))?;
+    let (&sample_count, rest) = rest
+        .split_first_chunk::<2>()
+        .ok_or_else(|| truncated(header_len))?;
+
+    let sample_len = 4 + 4 * channel_count;
+    let sample_count = usize::from(u16::from_le_bytes(sample_count));
+    let expected = header_len + sample_count * sample_len;
+    if datagram.len() != expected {
+        return Err(truncated(expected));
+    }
+
+    let base = i64::from_le_bytes(base);
+    let channels: Vec<Option<Channel>> = ids.iter().map(|&id| channel_from_id(id)).collect();
+    rest.chunks_exact(sample_len)
+        .map(|sample| {
+            let (&delta, values) = sample
+                .split_first_chunk::<4>()
+                .ok_or_else(|| truncated(expected))?;
+            let micros = base.saturating_add(i64::from(u32::from_le_bytes(delta)));
+
+            let channels = channels
+                .iter()
+                .zip(values.chunks_exact(4))
+                .filter_map(|(&channel, value)| {
+                    let value = f32::from_le_bytes(value.try_into().ok()?);
+                    Some((channel?, f64::from(value)))
+                })
+                .collect();
+
+            Ok(Measurement {
+                timestamp: from_micros(micros)?,
+                channels,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TelemetryError, decode};
+    use crate::tabs::{Channel, Measurement};
+
+    const MICROS: i64 = 1_735_743_600_000_000;
+
+    fn single(datagram: &[u8]) -> Measurement {
+        let mut measurements = decode(datagram).expect("datagrama válido");
+        assert_eq!(measurements.len(), 1, "una sola muestra");
+        measurements.pop().expect("una sola muestra")
+    }
+
+    fn binary_v1(channels: &[(u8, f32)]) -> Vec<u8> {
+        let mut datagram = vec![0x01];
+        datagram.extend(MICROS.to_le_bytes());
+        datagram.push(u8::try_from(channels.len()).expect("pocos canales"));
+        for &(id, value) in channels {
+            datagram.push(id);
+            datagram.extend(value.to_le_bytes());
+        }
+        datagram
+    }
+
+    #[test]
+    fn decodes_text_lines() {
+        let measurements = decode(
+            b"2025-01-01T12:00:00-03:00 vout=12.5 VIN=5 rpm=3\n\
+              2025-01-01T12:00:01-03:00 11.75\n\0\0\0",
+        )
+        .expect("datagrama válido");
+
+        let [first, second] = measurements.as_slice() else {
+            panic!("se esperaban dos muestras");
+        };
+        assert_eq!(
+            first.timestamp.timestamp_micros(),
+            MICROS,
+            "marca de tiempo"
+        );
+        assert_eq!(first.get(Channel::Vout), Some(12.5), "Vout");
+        assert_eq!(
+            first.get(Channel::Vin),
+            Some(5.0),
+            "sin distinguir mayúsculas"
+        );
+        assert_eq!(first.channels.len(), 2, "el canal desconocido se ignora");
+        assert_eq!(
+            second.get(Channel::Vout),
+            Some(11.75),
+            "el formato anterior es Vout"
+        );
+    }
+
+    #[test]
+    fn decodes_json_objects() {
+        let measurement =
+            single(br#"{"ts": "2025-01-01T12:00:00-03:00", "vout": 12.5, "iin": null, "x": "?"}"#);
+        assert_eq!(measurement.timestamp.timestamp_micros(), MICROS, "RFC 3339");
+        assert_eq!(measurement.get(Channel::Vout), Some(12.5), "Vout");
+        assert_eq!(measurement.get(Channel::Iin), None, "`null` se ignora");
+
+        let measurement = single(format!(r#"{{"timestamp": {MICROS}, "vin": 5}}"#).as_bytes());
+        assert_eq!(
+            measurement.timestamp.timestamp_micros(),
+            MICROS,
+            "µs desde la época"
+        );
+        assert_eq!(measurement.get(Channel::Vin), Some(5.0), "entero como f64");
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        for line in [
+            r#"{"vout": 12.5}"#,
+            r#"{"ts": 0, "vout": "12.5"}"#,
+            r#"{"ts": true}"#,
+            r#"{"ts": 0, "vout": 12.5"#,
+        ] {
+            assert!(
+                matches!(decode(line.as_bytes()), Err(TelemetryError::Json(_))),
+                "`{line}`"
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_binary_v1() {
+        let measurement = single(&binary_v1(&[(0, 12.5), (1, 5.0), (9, 1.0)]));
+        assert_eq!(
+            measurement.timestamp.timestamp_micros(),
+            MICROS,
+            "marca de tiempo"
+        );
+        assert_eq!(measurement.get(Channel::Vout), Some(12.5), "Vout");
+        assert_eq!(measurement.get(Channel::Vin), Some(5.0), "Vin");
+        assert_eq!(
+            measurement.channels.len(),
+            2,
+            "el canal desconocido se descarta"
+        );
+    }
+
+    #[test]
+    fn checks_binary_v1_length() {
+        let datagram = binary_v1(&[(0, 12.5), (1, 5.0)]);
+        let truncated = datagram
+            .get(..datagram.len() - 1)
+            .expect("datagrama no vacío");
+        assert!(
+            matches!(
+                decode(truncated),
+                Err(TelemetryError::Length {
+                    expected: 20,
+                    received: 19,
+                })
+            ),
+            "falta un byte"
+        );
+
+        let mut padded = datagram.clone();
+        padded.push(0);
+        assert!(
+            matches!(
+                decode(&padded),
+                Err(TelemetryError::Length {
+                    expected: 20,
+                    received: 21,
+                })
+            ),
+            "sobra un byte"
+        );
+
+        assert!(
+            matches!(
+                decode(datagram.get(..5).expect("datagrama no vacío")),
+                Err(TelemetryError::Length { expected: 10, .. })
+            ),
+            "cabecera incompleta"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_datagrams() {
+        assert!(matches!(decode(b""), Err(TelemetryError::Empty)), "vacío");
+        assert!(
+            matches!(
+                decode(&[0x07, 0]),
+                Err(TelemetryError::UnsupportedVersion(0x07))
+            ),
+            "versión desconocida"
+        );
+    }
+
+    fn binary_v2(ids: &[u8], samples: &[(u32, &[f32])]) -> Vec<u8> {
+        let mut datagram = vec![0x02];
+        datagram.extend(MICROS.to_le_bytes());
+        datagram.push(u8::try_from(ids.len()).expect("pocos canales"));
+        datagram.extend(ids);
+        datagram.extend(
+            u16::try_from(samples.len())
+                .expect("pocas muestras")
+                .to_le_bytes(),
+        );
+        for &(delta, values) in samples {
+            datagram.extend(delta.to_le_bytes());
+            for value in values {
+                datagram.extend(value.to_le_bytes());
+            }
+        }
+        datagram
+    }
+
+    #[test]
+    fn decodes_binary_v2_blocks() {
+        let datagram = binary_v2(
+            &[0, 4, 9],
+            &[
+                (0, &[12.0, 40.0, 1.0]),
+                (2000, &[12.5, 41.0, 1.0]),
+                (4000, &[13.0, 42.0, 1.0]),
+            ],
+        );
+        let measurements = decode(&datagram).expect("datagrama válido");
+
+        assert_eq!(measurements.len(), 3, "tres muestras");
+        for (i, (measurement, vout)) in measurements.iter().zip([12.0, 12.5, 13.0]).enumerate() {
+            let delta = 2000 * i64::try_from(i).expect("índice pequeño");
+            assert_eq!(
+                measurement.timestamp.timestamp_micros(),
+                MICROS + delta,
+                "marca de tiempo de la muestra {i}"
+            );
+            assert_eq!(
+                measurement.get(Channel::Vout),
+                Some(vout),
+                "Vout de la muestra {i}"
+            );
+            assert_eq!(
+                measurement.channels.len(),
+                2,
+                "el canal desconocido se descarta en la muestra {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn checks_binary_v2_length() {
+        let datagram = binary_v2(&[0, 1], &[(0, &[12.0, 5.0]), (1000, &[12.1, 5.0])]);
+        assert_eq!(datagram.len(), 38, "14 de cabecera y 2 × 12 de muestras");
+
+        let truncated = datagram.get(..37).expect("datagrama completo");
+        assert!(
+            matches!(
+                decode(truncated),
+                Err(TelemetryError::Length {
+                    expected: 38,
+                    received: 37,
+                })
+            ),
+            "muestra incompleta"
+        );
+        assert!(
+            matches!(
+                decode(datagram.get(..12).expect("datagrama completo")),
+                Err(TelemetryError::Length { expected: 14, .. })
+            ),
+            "lista de canales incompleta"
+        );
+
+        let empty = binary_v2(&[0], &[]);
+        assert!(
+            decode(&empty).is_ok_and(|measurements| measurements.is_empty()),
+            "un bloque sin muestras es válido"
+        );
+    }
+}
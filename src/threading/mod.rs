@@ -3,24 +3,40 @@ use std::{
     net::{IpAddr, SocketAddr},
     str::FromStr as _,
     sync::mpsc::{Receiver, Sender, TryRecvError},
+    time::Duration,
 };
 
 use anyhow::Result;
-use log::debug;
+use log::{debug, trace, warn};
 use tokio::net::UdpSocket;
 
-use crate::tabs::Measurement;
+use crate::{
+    tabs::Measurement,
+    telemetry::{self, TelemetryError},
+};
+
+mod serial;
+pub use serial::{LinkHealth, ProbeMatch, SerialEvent, SerialRequest, SerialThread, Setting};
 
 pub enum ThreadMessage {
-    StartConnection { address: String, port: u16 },
+    StartConnection {
+        address: String,
+        port: u16,
+    },
     Disconnect,
-    Data(Measurement),
+    /// Avisa al monitor que la interfaz se cierra y termina la conexión.
+    Stop,
+    /// El aviso de cierre ya se envió al monitor.
+    Stopped,
+    /// Muestras de un datagrama del monitor, en orden.
+    Batch(Vec<Measurement>),
+    /// Datagrama del monitor que no se pudo decodificar; se descarta.
+    Malformed(TelemetryError),
     ConnectionEstablished,
     None,
 }
 
 struct Connection {
-    #[expect(unused)]
     pub remote_addr: SocketAddr,
     pub socket: UdpSocket,
 }
@@ -39,30 +55,45 @@ pub struct MessagingThread {
     tx: Sender<ThreadMessage>,
 
     connection: Option<Connection>,
+    /// Espacio para el mayor datagrama UDP posible.
+    buf: Box<[u8]>,
 }
 
 impl MessagingThread {
+    /// Espera máxima por un datagrama antes de atender los pedidos de la
+    /// interfaz, para que un monitor silencioso no los bloquee.
+    const POLL_INTERVAL: Duration = Duration::from_millis(20);
+    const MAX_DATAGRAM: usize = 65_507;
+
     pub fn new(rx: Receiver<ThreadMessage>, tx: Sender<ThreadMessage>) -> Self {
         Self {
             rx,
             tx,
             connection: None,
+            buf: vec![0; Self::MAX_DATAGRAM].into_boxed_slice(),
         }
     }
 
     #[expect(clippy::missing_errors_doc)]
     pub async fn poll_messages(&mut self) -> Result<()> {
-        if let Some(connection) = &self.connection {
-            connection.socket.readable().await?;
+        if let Some(connection) = &self.connection
+            && let Ok(readable) =
+                tokio::time::timeout(Self::POLL_INTERVAL, connection.socket.readable()).await
+        {
+            readable?;
 
-            let mut buf = [0; 1024];
-            match connection.socket.try_recv(&mut buf) {
+            match connection.socket.try_recv(&mut self.buf) {
                 Ok(n) => {
-                    let data = str::from_utf8(&buf)?;
-                    debug!("Recibidos {n} bytes desde el dispositivo: {data}",);
-
-                    let data = Measurement::from_str(data)?;
-                    self.tx.send(ThreadMessage::Data(data))?;
+                    let datagram = self.buf.get(..n).unwrap_or_default();
+                    trace!("Recibidos {n} bytes desde el dispositivo: {datagram:02X?}");
+
+                    match telemetry::decode(datagram) {
+                        Ok(measurements) => self.tx.send(ThreadMessage::Batch(measurements))?,
+                        Err(e) => {
+                            warn!("Datagrama del monitor descartado: {e}");
+                            self.tx.send(ThreadMessage::Malformed(e))?;
+                        }
+                    }
                 }
                 Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
                 Err(e) => {
@@ -101,6 +132,15 @@ impl MessagingThread {
             ThreadMessage::Disconnect => {
                 self.connection = None;
             }
+            ThreadMessage::Stop => {
+                if let Some(connection) = self.connection.take() {
+                    connection
+                        .socket
+                        .send_to(b"STOP", connection.remote_addr)
+                        .await?;
+                }
+                self.tx.send(ThreadMessage::Stopped)?;
+            }
             _ => {}
         }
 
@@ -0,0 +1,832 @@
+use std::{
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Error, Result, anyhow};
+use log::{debug, error, warn};
+
+use crate::ramp::{FrequencyRamp, HostRamp, RampProfile, RampShape};
+use crate::serialcomms::{
+    BAUDRATES, DeviceInfo, DeviceSettings, DutyQ9, FrequencyHz, SerialConfig, SerialLink,
+    arm_watchdog, attempt_handshake, emergency_stop, heartbeat, probe_device, ramp_duty,
+    ramp_frequency, read_input_voltage, read_settings, reset_framing, set_duty, set_frequency,
+    usb_serial_number,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    DutyCycle,
+    Frequency,
+}
+
+impl Setting {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::DutyCycle => "duty cycle",
+            Self::Frequency => "frecuencia",
+        }
+    }
+}
+
+pub enum SerialRequest {
+    Connect {
+        port_name: String,
+        config: SerialConfig,
+    },
+    Disconnect,
+    SetDuty(DutyQ9),
+    /// Cambio del ciclo de trabajo con el perfil de rampa, medido desde el
+    /// último valor aplicado. Las rampas lineales las ejecuta el firmware si
+    /// lo admite; el resto se aplica desde este hilo paso a paso.
+    RampDuty {
+        end: DutyQ9,
+        profile: RampProfile,
+    },
+    SetFrequency(FrequencyHz),
+    /// Rampa de frecuencia desde el último valor aplicado, ejecutada por el
+    /// firmware si la admite.
+    RampFrequency {
+        end: FrequencyHz,
+        profile: FrequencyRamp,
+    },
+    ReadSettings,
+    ReadInputVoltage,
+    Probe,
+    /// Corta la salida, adelantándose a los ajustes que estén en cola.
+    EmergencyStop,
+    /// Lleva el ciclo de trabajo a cero con una rampa y cierra el puerto.
+    Shutdown,
+    /// Cierra el puerto dejando el convertidor en marcha, sin watchdog.
+    Detach,
+}
+
+impl SerialRequest {
+    fn is_setpoint(&self) -> bool {
+        matches!(
+            self,
+            Self::SetDuty(_)
+                | Self::RampDuty { .. }
+                | Self::SetFrequency(_)
+                | Self::RampFrequency { .. }
+        )
+    }
+}
+
+/// Puerto en el que un controlador SEPIC respondió al handshake.
+#[derive(Clone, Debug)]
+pub struct ProbeMatch {
+    pub port_name: String,
+    pub baudrate: u32,
+    pub info: DeviceInfo,
+}
+
+/// Estado del enlace según las respuestas a los heartbeats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkHealth {
+    Healthy,
+    Late {
+        missed: u32,
+    },
+    /// Se perdieron suficientes heartbeats como para suponer que el
+    /// dispositivo ya no responde.
+    Unresponsive,
+}
+
+pub enum SerialEvent {
+    Connected {
+        port_name: String,
+        info: DeviceInfo,
+        settings: DeviceSettings,
+    },
+    OpenFailed {
+        port_name: String,
+        error: Error,
+    },
+    HandshakeFailed {
+        port_name: String,
+        error: Error,
+    },
+    Applied(Setting),
+    Failed {
+        setting: Setting,
+        error: Error,
+    },
+    Readback(DeviceSettings),
+    ReadbackFailed(Error),
+    InputVoltage(f32),
+    InputVoltageFailed(Error),
+    ProbeFinished(Vec<ProbeMatch>),
+    /// El puerto desapareció del sistema; se intentará reconectar.
+    LinkLost {
+        port_name: String,
+    },
+    /// El dispositivo volvió con la frecuencia restaurada; `settings` trae el
+    /// ciclo de trabajo con el que arrancó y `duty_cycle` el que tenía antes de
+    /// perderse, al que la interfaz lo lleva con su perfil de rampa.
+    Reconnected {
+        port_name: String,
+        info: DeviceInfo,
+        settings: DeviceSettings,
+        duty_cycle: DutyQ9,
+    },
+    LinkHealth(LinkHealth),
+    Stopped,
+    StopFailed(Error),
+    ShutdownFinished(Result<()>),
+}
+
+/// Dispositivo al que se debe mantener conectado, con los últimos valores
+/// aplicados para restaurarlos si se reconecta.
+struct Target {
+    port_name: String,
+    config: SerialConfig,
+    serial_number: Option<String>,
+    duty_cycle: DutyQ9,
+    frequency: FrequencyHz,
+}
+
+/// Hilo dueño del puerto serial. Recibe comandos desde la interfaz y le
+/// reporta los resultados, de modo que una respuesta lenta del dispositivo no
+/// bloquee el dibujado de la ventana.
+pub struct SerialThread {
+    rx: Receiver<SerialRequest>,
+    tx: Sender<SerialEvent>,
+
+    link: Option<SerialLink>,
+    target: Option<Target>,
+    readback_due: Option<Instant>,
+    watch_due: Option<Instant>,
+    heartbeat_due: Option<Instant>,
+    missed_heartbeats: u32,
+    duty_ramp: HostRamp<DutyQ9>,
+    frequency_ramp: HostRamp<FrequencyHz>,
+}
+
+impl SerialThread {
+    const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+    const MAX_MISSED_HEARTBEATS: u32 = 3;
+    /// Duración de la rampa a cero al cerrar la interfaz, en ms.
+    const SHUTDOWN_TSPAN: u32 = 1000;
+
+    pub fn new(rx: Receiver<SerialRequest>, tx: Sender<SerialEvent>) -> Self {
+        Self {
+            rx,
+            tx,
+            link: None,
+            target: None,
+            readback_due: None,
+            watch_due: None,
+            heartbeat_due: None,
+            missed_heartbeats: 0,
+            duty_ramp: HostRamp::default(),
+            frequency_ramp: HostRamp::default(),
+        }
+    }
+
+    /// Atiende solicitudes hasta que la interfaz cierra su extremo del canal.
+    pub fn run(mut self) {
+        loop {
+            let deadline = [
+                self.readback_due,
+                self.watch_due,
+                self.heartbeat_due,
+                self.duty_ramp.next_due(),
+                self.frequency_ramp.next_due(),
+            ]
+            .into_iter()
+            .flatten()
+            .min();
+            let request = match deadline {
+                Some(due) => self
+                    .rx
+                    .recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => self.rx.recv().map_err(RecvTimeoutError::from),
+            };
+
+            let result = match request {
+                Ok(request) => self.handle_pending(request),
+                Err(RecvTimeoutError::Timeout) => self.on_timer(),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            if let Err(e) = result {
+                error!("Error en la comunicación con la interfaz: {e}");
+                break;
+            }
+        }
+
+        // La interfaz terminó sin pedir el apagado, posiblemente por un
+        // pánico: no se deja el convertidor en marcha.
+        if self.link.is_some() {
+            warn!("La interfaz se cerró sin apagar el convertidor, llevando el ciclo a cero");
+            if let Err(e) = self.ramp_to_zero() {
+                error!("No se pudo apagar el convertidor: {e}");
+            }
+        }
+
+        debug!("Hilo serial finalizado");
+    }
+
+    fn on_timer(&mut self) -> Result<()> {
+        let now = Instant::now();
+
+        if self.readback_due.is_some_and(|due| due <= now) {
+            self.readback_due = None;
+            self.read_back()?;
+        }
+
+        if self.watch_due.is_some_and(|due| due <= now) {
+            self.watch_due = Some(now + Self::WATCH_INTERVAL);
+            self.watch_port()?;
+        }
+
+        if let Some(duty) = self.duty_ramp.take_due(now)
+            && self.ramp_step(Setting::DutyCycle, |link| set_duty(link, duty))?
+        {
+            self.remember(|target| target.duty_cycle = duty);
+        }
+
+        if let Some(freq) = self.frequency_ramp.take_due(now)
+            && self.ramp_step(Setting::Frequency, |link| set_frequency(link, freq))?
+        {
+            self.remember(|target| target.frequency = freq);
+        }
+
+        if self.heartbeat_due.is_some_and(|due| due <= now) {
+            self.send_heartbeat()?;
+        }
+
+        Ok(())
+    }
+
+    fn send(&self, event: SerialEvent) -> Result<()> {
+        self.tx
+            .send(event)
+            .map_err(|_err| anyhow!("El canal de eventos seriales está cerrado"))
+    }
+
+    /// Atiende la solicitud recibida junto con las que ya estaban en cola. Un
+    /// paro de emergencia se atiende primero y descarta los ajustes que se
+    /// pidieron antes que él.
+    fn handle_pending(&mut self, first: SerialRequest) -> Result<()> {
+        let mut pending: Vec<_> = std::iter::once(first).chain(self.rx.try_iter()).collect();
+
+        if let Some(last_stop) = pending
+            .iter()
+            .rposition(|request| matches!(request, SerialRequest::EmergencyStop))
+        {
+            let later = pending.split_off(last_stop + 1);
+            pending.retain(|request| {
+                !request.is_setpoint() && !matches!(request, SerialRequest::EmergencyStop)
+            });
+            pending.extend(later);
+            self.stop()?;
+        }
+
+        pending
+            .into_iter()
+            .try_for_each(|request| self.handle(request))
+    }
+
+    fn handle(&mut self, request: SerialRequest) -> Result<()> {
+        match request {
+            SerialRequest::Connect { port_name, config } => self.connect(port_name, config),
+            SerialRequest::Disconnect => {
+                self.close();
+                Ok(())
+            }
+            SerialRequest::SetDuty(duty) => {
+                self.duty_ramp.clear();
+                self.apply(Setting::DutyCycle, Duration::ZERO, |link| {
+                    set_duty(link, duty)
+                })?;
+                self.remember(|target| target.duty_cycle = duty);
+                Ok(())
+            }
+            SerialRequest::RampDuty { end, profile } => self.ramp_duty(end, profile),
+            SerialRequest::SetFrequency(freq) => {
+                self.frequency_ramp.clear();
+                self.apply(Setting::Frequency, Duration::ZERO, |link| {
+                    set_frequency(link, freq)
+                })?;
+                self.remember(|target| target.frequency = freq);
+                Ok(())
+            }
+            SerialRequest::RampFrequency { end, profile } => self.ramp_frequency(end, profile),
+            SerialRequest::ReadSettings => self.read_back(),
+            SerialRequest::ReadInputVoltage => {
+                let Some(link) = self.link.as_mut() else {
+                    return Ok(());
+                };
+                match read_input_voltage(link) {
+                    Ok(vin) => self.send(SerialEvent::InputVoltage(vin)),
+                    Err(e) => self.send(SerialEvent::InputVoltageFailed(e)),
+                }
+            }
+            SerialRequest::Probe => {
+                let matches = self.probe();
+                self.send(SerialEvent::ProbeFinished(matches))
+            }
+            SerialRequest::EmergencyStop => self.stop(),
+            SerialRequest::Shutdown => {
+                let result = self.ramp_to_zero();
+                self.close();
+                self.send(SerialEvent::ShutdownFinished(result))
+            }
+            SerialRequest::Detach => {
+                if let Some(link) = self.link.as_mut()
+                    && let Err(e) = arm_watchdog(link, 0)
+                {
+                    warn!("No se pudo desactivar el watchdog del dispositivo: {e}");
+                }
+                self.close();
+                Ok(())
+            }
+        }
+    }
+
+    fn ramp_to_zero(&mut self) -> Result<()> {
+        self.duty_ramp.clear();
+
+        let (Some(link), Some(target)) = (self.link.as_mut(), self.target.as_mut()) else {
+            return Ok(());
+        };
+
+        let result = if link.capabilities().ramp {
+            ramp_duty(link, target.duty_cycle, DutyQ9::ZERO, Self::SHUTDOWN_TSPAN)
+        } else {
+            // Sin rampas en el firmware la trayectoria se recorre desde aquí;
+            // el hilo queda ocupado, pero ya no se esperan otras solicitudes.
+            let tspan = Duration::from_millis(u64::from(Self::SHUTDOWN_TSPAN));
+            ramp_blocking(link, target.duty_cycle, tspan)
+        };
+        if let Err(e) = result {
+            warn!("No se pudo bajar el ciclo con una rampa ({e}), deteniendo el convertidor");
+            emergency_stop(link)?;
+        }
+        target.duty_cycle = DutyQ9::ZERO;
+        debug!("Convertidor apagado");
+
+        Ok(())
+    }
+
+    /// Lleva el ciclo de trabajo a `end` partiendo del último valor aplicado,
+    /// que durante una rampa desde este hilo es el paso en curso.
+    fn ramp_duty(&mut self, end: DutyQ9, profile: RampProfile) -> Result<()> {
+        self.duty_ramp.clear();
+
+        let (Some(link), Some(target)) = (self.link.as_ref(), self.target.as_ref()) else {
+            return self.report_not_connected(Setting::DutyCycle);
+        };
+        let start = target.duty_cycle;
+        let firmware_ramp = profile.shape == RampShape::Linear && link.capabilities().ramp;
+
+        let Some(tspan) = profile.duration_for(start, end) else {
+            self.apply(Setting::DutyCycle, Duration::ZERO, |link| {
+                set_duty(link, end)
+            })?;
+            self.remember(|target| target.duty_cycle = end);
+            return Ok(());
+        };
+
+        if !firmware_ramp {
+            let schedule = profile.shape.schedule(start, end, tspan);
+            debug!(
+                "Rampa {} de {start} a {end} en {} pasos",
+                profile.shape.label().to_lowercase(),
+                schedule.len()
+            );
+            self.duty_ramp.start(schedule);
+            return Ok(());
+        }
+
+        let tspan_ms = u32::try_from(tspan.as_millis()).unwrap_or(u32::MAX);
+        self.apply(Setting::DutyCycle, tspan, |link| {
+            ramp_duty(link, start, end, tspan_ms)
+        })?;
+        self.remember(|target| target.duty_cycle = end);
+        Ok(())
+    }
+
+    /// Lleva la frecuencia a `end` partiendo del último valor aplicado, de
+    /// modo que al cambiar el destino a mitad de una rampa no haya saltos.
+    fn ramp_frequency(&mut self, end: FrequencyHz, profile: FrequencyRamp) -> Result<()> {
+        self.frequency_ramp.clear();
+
+        let (Some(link), Some(target)) = (self.link.as_ref(), self.target.as_ref()) else {
+            return self.report_not_connected(Setting::Frequency);
+        };
+        let start = target.frequency;
+        if !link.capabilities().frequency_ramp {
+            let schedule = profile.schedule(start, end);
+            debug!(
+                "Rampa de frecuencia de {start} a {end} en {} pasos",
+                schedule.len()
+            );
+            self.frequency_ramp.start(schedule);
+            return Ok(());
+        }
+
+        self.apply(
+            Setting::Frequency,
+            Duration::from_millis(u64::from(profile.duration_ms)),
+            |link| ramp_frequency(link, start, end, profile.duration_ms),
+        )?;
+        self.remember(|target| target.frequency = end);
+        Ok(())
+    }
+
+    fn report_not_connected(&self, setting: Setting) -> Result<()> {
+        self.send(SerialEvent::Failed {
+            setting,
+            error: anyhow!("No hay un dispositivo conectado"),
+        })
+    }
+
+    fn host_ramp_is_empty(&self, setting: Setting) -> bool {
+        match setting {
+            Setting::DutyCycle => self.duty_ramp.is_empty(),
+            Setting::Frequency => self.frequency_ramp.is_empty(),
+        }
+    }
+
+    fn cancel_host_ramp(&mut self, setting: Setting) {
+        match setting {
+            Setting::DutyCycle => self.duty_ramp.clear(),
+            Setting::Frequency => self.frequency_ramp.clear(),
+        }
+    }
+
+    /// Aplica un paso de una rampa ejecutada desde este hilo. Devuelve si el
+    /// paso se aplicó; ante un error la rampa se cancela.
+    fn ramp_step(
+        &mut self,
+        setting: Setting,
+        command: impl FnOnce(&mut SerialLink) -> Result<()>,
+    ) -> Result<bool> {
+        let Some(link) = self.link.as_mut() else {
+            self.cancel_host_ramp(setting);
+            return Ok(false);
+        };
+
+        if let Err(e) = command(link) {
+            self.cancel_host_ramp(setting);
+            error!("Se interrumpió la rampa de {}: {e}", setting.label());
+            self.send(SerialEvent::Failed { setting, error: e })?;
+            return Ok(false);
+        }
+
+        if self.host_ramp_is_empty(setting) {
+            self.readback_due = Some(Instant::now());
+            self.send(SerialEvent::Applied(setting))?;
+        }
+
+        Ok(true)
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.duty_ramp.clear();
+
+        // Aunque el enlace esté caído, al reconectar no se debe restaurar el
+        // ciclo de trabajo anterior al paro.
+        if let Some(target) = self.target.as_mut() {
+            target.duty_cycle = DutyQ9::ZERO;
+        }
+
+        let Some(link) = self.link.as_mut() else {
+            return self.send(SerialEvent::StopFailed(anyhow!(
+                "No hay un dispositivo conectado"
+            )));
+        };
+
+        match emergency_stop(link) {
+            Ok(()) => {
+                warn!("Salida del convertidor detenida");
+                self.readback_due = Some(Instant::now());
+                self.send(SerialEvent::Stopped)
+            }
+            Err(e) => {
+                error!("No se pudo detener el convertidor: {e}");
+                self.send(SerialEvent::StopFailed(e))
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(link) = self.link.as_mut()
+            && let Err(e) = reset_framing(link)
+        {
+            warn!("No se pudo devolver el dispositivo al formato sin verificación: {e}");
+        }
+        self.link = None;
+        self.target = None;
+        self.readback_due = None;
+        self.watch_due = None;
+        self.heartbeat_due = None;
+        self.missed_heartbeats = 0;
+        self.duty_ramp.clear();
+        self.frequency_ramp.clear();
+    }
+
+    /// Programa el primer heartbeat de un enlace recién establecido. El
+    /// firmware anterior a la identificación no conoce `QRY` ni `HBT`, así que
+    /// con él no se vigila el enlace.
+    fn start_heartbeat(&mut self, config: &SerialConfig, info: &DeviceInfo) {
+        self.missed_heartbeats = 0;
+        if info.is_legacy() {
+            debug!("Heartbeat desactivado: el firmware no admite consultas");
+            self.heartbeat_due = None;
+            return;
+        }
+        self.heartbeat_due = config
+            .heartbeat_interval()
+            .map(|interval| Instant::now() + interval);
+    }
+
+    fn send_heartbeat(&mut self) -> Result<()> {
+        let (Some(link), Some(interval)) = (
+            self.link.as_mut(),
+            self.target
+                .as_ref()
+                .and_then(|target| target.config.heartbeat_interval()),
+        ) else {
+            self.heartbeat_due = None;
+            return Ok(());
+        };
+        self.heartbeat_due = Some(Instant::now() + interval);
+
+        match heartbeat(link) {
+            Ok(()) if self.missed_heartbeats == 0 => Ok(()),
+            Ok(()) => {
+                debug!("El dispositivo volvió a responder");
+                if self.missed_heartbeats >= Self::MAX_MISSED_HEARTBEATS {
+                    // El watchdog pudo haber llevado el ciclo de trabajo a cero.
+                    self.readback_due = Some(Instant::now());
+                }
+                self.missed_heartbeats = 0;
+                self.send(SerialEvent::LinkHealth(LinkHealth::Healthy))
+            }
+            Err(e) => {
+                self.missed_heartbeats += 1;
+                let missed = self.missed_heartbeats;
+                warn!("El dispositivo no respondió al heartbeat ({missed} seguidos): {e}");
+
+                let health = if missed >= Self::MAX_MISSED_HEARTBEATS {
+                    LinkHealth::Unresponsive
+                } else {
+                    LinkHealth::Late { missed }
+                };
+                self.send(SerialEvent::LinkHealth(health))
+            }
+        }
+    }
+
+    fn remember(&mut self, update: impl FnOnce(&mut Target)) {
+        if self.link.is_some()
+            && let Some(target) = self.target.as_mut()
+        {
+            update(target);
+        }
+    }
+
+    /// Detecta la desconexión del puerto y, cuando reaparece el mismo
+    /// dispositivo, repite el handshake y restaura los últimos valores.
+    fn watch_port(&mut self) -> Result<()> {
+        let Some(target) = self.target.as_ref() else {
+            return Ok(());
+        };
+
+        let ports = match serialport::available_ports() {
+            Ok(ports) => ports,
+            Err(e) => {
+                error!("No se pudo obtener la lista de puertos: {e}");
+                return Ok(());
+            }
+        };
+
+        if self.link.is_some() {
+            if !ports.iter().any(|port| port.port_name == target.port_name) {
+                warn!("Se perdió la conexión con `{}`", target.port_name);
+                self.link = None;
+                self.readback_due = None;
+                self.heartbeat_due = None;
+                return self.send(SerialEvent::LinkLost {
+                    port_name: target.port_name.clone(),
+                });
+            }
+            return Ok(());
+        }
+
+        let Some(port) = ports.iter().find(|port| match &target.serial_number {
+            Some(serial_number) => usb_serial_number(port) == Some(serial_number.as_str()),
+            None => port.port_name == target.port_name,
+        }) else {
+            return Ok(());
+        };
+
+        let port_name = port.port_name.clone();
+        let Ok(serial) = target.config.open(&port_name) else {
+            return Ok(());
+        };
+
+        let mut link = SerialLink::new(serial);
+        let (info, device) = match attempt_handshake(&mut link) {
+            Ok(handshake) => handshake,
+            Err(e) => {
+                debug!("El dispositivo reapareció en `{port_name}` pero no responde: {e}");
+                return Ok(());
+            }
+        };
+
+        let settings = DeviceSettings {
+            frequency: target.frequency,
+            duty_cycle: device.duty_cycle,
+        };
+        let duty_cycle = target.duty_cycle;
+        if let Err(e) = set_frequency(&mut link, settings.frequency) {
+            warn!("No se pudo restaurar la frecuencia en `{port_name}`: {e}");
+            return Ok(());
+        }
+        let config = target.config.clone();
+        arm_link_watchdog(&mut link, &config);
+
+        debug!("Reconectado a `{port_name}`");
+        self.link = Some(link);
+        self.start_heartbeat(&config, &info);
+        self.readback_due = Some(Instant::now());
+        if let Some(target) = self.target.as_mut() {
+            target.port_name.clone_from(&port_name);
+            target.duty_cycle = settings.duty_cycle;
+        }
+
+        self.send(SerialEvent::Reconnected {
+            port_name,
+            info,
+            settings,
+            duty_cycle,
+        })
+    }
+
+    /// Prueba el handshake en cada puerto disponible y con cada velocidad
+    /// admitida, deteniéndose en la primera velocidad que responde.
+    fn probe(&mut self) -> Vec<ProbeMatch> {
+        self.close();
+
+        let ports = match serialport::available_ports() {
+            Ok(ports) => ports,
+            Err(e) => {
+                error!("No se pudo obtener la lista de puertos: {e}");
+                return Vec::new();
+            }
+        };
+
+        let mut matches = Vec::new();
+        for port in ports {
+            for baudrate in BAUDRATES {
+                debug!("Probando `{}` a {baudrate} baud", port.port_name);
+
+                let Ok(serial) = SerialConfig::with_baudrate(baudrate).open(&port.port_name) else {
+                    break;
+                };
+
+                if let Ok(info) = probe_device(&mut SerialLink::new(serial)) {
+                    debug!("Controlador encontrado en `{}`", port.port_name);
+                    matches.push(ProbeMatch {
+                        port_name: port.port_name.clone(),
+                        baudrate,
+                        info,
+                    });
+                    break;
+                }
+            }
+        }
+
+        matches
+    }
+
+    fn connect(&mut self, port_name: String, config: SerialConfig) -> Result<()> {
+        self.close();
+
+        let port = match config.open(&port_name) {
+            Ok(port) => port,
+            Err(e) => {
+                error!("No se pudo abrir el puerto `{port_name}`: `{e:?}`");
+                return self.send(SerialEvent::OpenFailed {
+                    port_name,
+                    error: Error::new(e),
+                });
+            }
+        };
+
+        let mut link = SerialLink::new(port);
+        match attempt_handshake(&mut link) {
+            Ok((info, settings)) => {
+                arm_link_watchdog(&mut link, &config);
+                self.start_heartbeat(&config, &info);
+
+                let serial_number = serialport::available_ports()
+                    .unwrap_or_default()
+                    .iter()
+                    .find(|port| port.port_name == port_name)
+                    .and_then(usb_serial_number)
+                    .map(str::to_owned);
+
+                self.link = Some(link);
+                self.target = Some(Target {
+                    port_name: port_name.clone(),
+                    config,
+                    serial_number,
+                    duty_cycle: settings.duty_cycle,
+                    frequency: settings.frequency,
+                });
+                self.watch_due = Some(Instant::now() + Self::WATCH_INTERVAL);
+                self.send(SerialEvent::Connected {
+                    port_name,
+                    info,
+                    settings,
+                })
+            }
+            Err(e) => {
+                error!("Falló el handshake con el dispositivo: {e:?}");
+                self.send(SerialEvent::HandshakeFailed {
+                    port_name,
+                    error: e,
+                })
+            }
+        }
+    }
+
+    /// Envía un ajuste y programa la lectura de verificación, que en el caso
+    /// de las rampas se posterga hasta que estas terminan.
+    fn apply(
+        &mut self,
+        setting: Setting,
+        settle: Duration,
+        command: impl FnOnce(&mut SerialLink) -> Result<()>,
+    ) -> Result<()> {
+        let Some(link) = self.link.as_mut() else {
+            return self.report_not_connected(setting);
+        };
+
+        match command(link) {
+            Ok(()) => {
+                let due = Instant::now() + settle;
+                self.readback_due = Some(self.readback_due.map_or(due, |prev| prev.max(due)));
+                self.send(SerialEvent::Applied(setting))
+            }
+            Err(e) => {
+                error!("No se pudo actualizar {}: {e}", setting.label());
+                self.send(SerialEvent::Failed { setting, error: e })
+            }
+        }
+    }
+
+    fn read_back(&mut self) -> Result<()> {
+        let Some(link) = self.link.as_mut() else {
+            return Ok(());
+        };
+
+        match read_settings(link) {
+            Ok(Some(settings)) => self.send(SerialEvent::Readback(settings)),
+            Ok(None) => {
+                debug!("El dispositivo no reporta sus valores, se omite la verificación");
+                Ok(())
+            }
+            Err(e) => {
+                error!("No se pudieron leer los valores del dispositivo: {e}");
+                self.send(SerialEvent::ReadbackFailed(e))
+            }
+        }
+    }
+}
+
+/// Configura el watchdog del firmware con el tiempo elegido para el puerto.
+fn arm_link_watchdog(link: &mut SerialLink, config: &SerialConfig) {
+    let timeout_ms = config.watchdog_timeout().unwrap_or_else(|| {
+        if config.watchdog_ms > 0 {
+            warn!("Watchdog desactivado: requiere un heartbeat de a lo sumo la mitad de su tiempo");
+        }
+        0
+    });
+
+    match arm_watchdog(link, timeout_ms) {
+        Ok(true) => {}
+        Ok(false) if timeout_ms == 0 => {}
+        Ok(false) => {
+            warn!("El dispositivo no tiene watchdog: seguirá operando si se cierra la interfaz");
+        }
+        Err(e) => warn!("No se pudo configurar el watchdog del dispositivo: {e}"),
+    }
+}
+
+/// Lleva el ciclo de trabajo de `start` a cero con una rampa lineal ejecutada
+/// paso a paso desde este hilo.
+fn ramp_blocking(link: &mut SerialLink, start: DutyQ9, tspan: Duration) -> Result<()> {
+    let begin = Instant::now();
+    for (offset, duty_cycle) in RampShape::Linear.schedule(start, DutyQ9::ZERO, tspan) {
+        std::thread::sleep((begin + offset).saturating_duration_since(Instant::now()));
+        set_duty(link, duty_cycle)?;
+    }
+    Ok(())
+}